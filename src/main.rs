@@ -18,9 +18,4 @@ fn main() {
             !state.should_quit()
         },
     );
-
-    //let html = mhw_scraper::load_page("https://mhworld.kiranico.com/monster/anjanath").unwrap();
-    //let worked = mhw_scraper::scrape_monster(&html).unwrap();
-
-    //println!("{:?}", worked);
 }
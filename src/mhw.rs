@@ -1,10 +1,29 @@
 #[macro_use]
 pub mod common;
+pub mod damage;
 
 pub mod armor;
+pub mod armor_sets;
+pub mod catalog;
+pub mod charms;
+pub mod data_source;
+pub mod database;
+pub mod decorations;
 pub mod entry_display;
+pub mod error_panel;
+pub mod history;
+pub mod item_sources;
 pub mod items;
+pub mod loadout;
+pub mod monsters;
 pub mod query;
+pub mod query_cache;
 pub mod query_filters;
+pub mod query_worker;
+pub mod scraping;
 pub mod search;
+pub mod set_search;
+pub mod skills;
+pub mod weapon_tree;
 pub mod weapons;
+pub mod wishlist;
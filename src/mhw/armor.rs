@@ -1,13 +1,17 @@
 use super::common::{
     fonts::*, rarity::*, CraftingCost, GuiDetails, MhwEvent, MhwWindowContents, SkillRank, Slot,
 };
+use super::decorations::draw_slots;
+use super::entry_display::EntryRequest;
+use super::search::SearchCategory;
+use super::wishlist::WishlistItem;
 use crate::widgets::table_view::*;
 use imgui::*;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt::{self, Debug, Display};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase")]
 pub enum ArmorType {
     Head,
@@ -23,7 +27,7 @@ impl Display for ArmorType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ArmorRank {
     Low,
@@ -58,6 +62,7 @@ pub struct SetInfo {
     pub name: String,
     pub rank: ArmorRank,
     pub pieces: Vec<i32>,
+    pub bonus: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -118,6 +123,8 @@ pub struct ArmorInfo {
     pub crafting_cache: SimpleTableDataModel,
     #[serde(skip)]
     pub other_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    pub upgrade_cache: SimpleTableDataModel,
 }
 
 impl ArmorInfo {
@@ -152,7 +159,8 @@ impl ArmorInfo {
             let iter = skills.iter();
             let skills_cache = &mut self.skills_cache;
             for skill in iter {
-                skills_cache.push(skill.skill_name.clone());
+                let link = EntryRequest::new(SearchCategory::Skills, skill.skill);
+                skills_cache.push_link(skill.skill_name.clone(), link);
                 skills_cache.push(skill.level.to_string());
             }
         }
@@ -165,13 +173,29 @@ impl ArmorInfo {
             self.crafting_cache.set_columns(2);
             let mats = &self.crafting.materials;
             for cost in mats {
-                self.crafting_cache.push(cost.item.name.clone());
+                let link = EntryRequest::new(SearchCategory::Items, cost.item.id);
+                self.crafting_cache.push_link(cost.item.name.clone(), link);
                 self.crafting_cache.push(cost.quantity.to_string());
             }
         }
         &self.crafting_cache
     }
 
+    // Upgrading only costs armor spheres, and the data doesn't say how many
+    pub fn upgrade_data(&mut self) -> &TableDataModel {
+        if self.upgrade_cache.is_empty() {
+            self.upgrade_cache.set_columns(2);
+            self.upgrade_cache.push("Defense".to_owned());
+            self.upgrade_cache
+                .push(format!("{} to {}", self.defense.base, self.defense.max));
+            self.upgrade_cache.push("Materials".to_owned());
+            self.upgrade_cache.push("Armor Spheres".to_owned());
+            self.upgrade_cache.push("Quantity".to_owned());
+            self.upgrade_cache.push("Not in the data".to_owned());
+        }
+        &self.upgrade_cache
+    }
+
     pub fn other_data(&mut self) -> &TableDataModel {
         if self.other_cache.is_empty() {
             self.other_cache.set_columns(2);
@@ -189,8 +213,8 @@ impl MhwWindowContents for ArmorInfo {
     fn build_window<'a>(
         &mut self,
         ui: &Ui<'a>,
-        _details: &mut GuiDetails,
-        _event_queue: &mut VecDeque<MhwEvent>, // will need this when linking between objects
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
     ) {
         //=======================================
         // Name/ID
@@ -203,6 +227,16 @@ impl MhwWindowContents for ArmorInfo {
             ui.text(id_string.as_str());
             ui.same_line(0.0);
             ui.text(format!("{} Rank {}", self.rank, self.type_val));
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Equip")) {
+                let request = EntryRequest::new(SearchCategory::Armor, self.id);
+                event_queue.push_back(MhwEvent::Equip(request));
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Wishlist")) {
+                let item = WishlistItem::from_armor(self);
+                event_queue.push_back(MhwEvent::AddToWishlist(item));
+            }
         });
 
         ui.columns(3, im_str!("armor_stats"), true);
@@ -231,33 +265,7 @@ impl MhwWindowContents for ArmorInfo {
             let text = im_str!("Slot Info");
             ui.text(text);
         });
-        for idx in 0..3 {
-            match self.slots.get(idx) {
-                Some(val) => match val.rank {
-                    1 => {
-                        ui.text("[1]");
-                        ui.same_line(0.0);
-                    }
-                    2 => {
-                        ui.text("[2]");
-                        ui.same_line(0.0);
-                    }
-                    3 => {
-                        ui.text("[3]");
-                        ui.same_line(0.0);
-                    }
-                    _ => {
-                        ui.text("[E]");
-                        ui.same_line(0.0);
-                    }
-                },
-                None => {
-                    ui.text("[-]");
-                    ui.same_line(0.0);
-                }
-            }
-        }
-        ui.new_line();
+        draw_slots(ui, &self.slots, details, event_queue);
 
         // Set Info
         ui.next_column();
@@ -265,29 +273,33 @@ impl MhwWindowContents for ArmorInfo {
             let text = im_str!("Armor Set");
             ui.text(text);
         });
-        ui.with_font(FONT_IDX_NORMAL, || {
-            let text = String::from(match &self.armor_set {
-                Some(ref set) => set.name.as_str(),
-                None => "<none>",
-            });
-
-            let imgstr = ImString::from(text);
-            ui.text(&imgstr);
+        ui.with_font(FONT_IDX_NORMAL, || match &self.armor_set {
+            Some(ref set) => {
+                let imgstr = ImString::new(set.name.as_str());
+                if ui.small_button(&imgstr) {
+                    let request = EntryRequest::new(SearchCategory::ArmorSets, set.id);
+                    event_queue.push_back(MhwEvent::RequestEntry(request));
+                }
+            }
+            None => ui.text("<none>"),
         });
 
         //=======================================
         // Lists section
         ui.columns(2, im_str!("armor_attribs"), true);
         ui.separator();
-        draw_table(ui, "Resistances", self.resistances_data());
+        draw_table(ui, "Resistances", self.resistances_data(), event_queue);
 
         ui.next_column();
-        draw_table(ui, "Skills", self.skills_data());
+        draw_table(ui, "Skills", self.skills_data(), event_queue);
 
         ui.next_column();
-        draw_table(ui, "Crafting", self.crafting_data());
+        draw_table(ui, "Crafting", self.crafting_data(), event_queue);
+        if self.defense.max > self.defense.base {
+            draw_table(ui, "Upgrade Cost", self.upgrade_data(), event_queue);
+        }
 
         ui.next_column();
-        draw_table(ui, "Other Attribs", self.other_data());
+        draw_table(ui, "Other Attribs", self.other_data(), event_queue);
     }
 }
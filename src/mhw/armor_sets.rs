@@ -0,0 +1,161 @@
+use super::armor::{ArmorRank, ArmorType, Defense, Resistances};
+use super::common::{
+    fonts::*, rarity::*, GuiDetails, MhwEvent, MhwWindowContents, SkillRank, Slot,
+};
+use super::entry_display::EntryRequest;
+use super::search::SearchCategory;
+use crate::widgets::table_view::*;
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmorSetBonusRank {
+    pub pieces: i32, // how many pieces of the set need to be worn
+    pub skill: SkillRank,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmorSetBonus {
+    pub id: i32,
+    pub name: String,
+    pub ranks: Vec<ArmorSetBonusRank>,
+}
+
+// The pieces listed by /armor/sets; only the parts the set view needs
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmorSetPiece {
+    pub id: i32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_val: ArmorType,
+    pub rarity: u32,
+    pub defense: Defense,
+    pub resistances: Resistances,
+    pub slots: Vec<Slot>,
+    pub skills: Vec<SkillRank>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmorSetInfo {
+    pub id: i32,
+    pub name: String,
+    pub rank: ArmorRank,
+    pub pieces: Vec<ArmorSetPiece>,
+    pub bonus: Option<ArmorSetBonus>,
+
+    // internal details
+    #[serde(skip)]
+    pieces_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    totals_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    bonus_cache: SimpleTableDataModel,
+}
+
+impl ArmorSetInfo {
+    fn pieces_data(&mut self) -> &TableDataModel {
+        if self.pieces_cache.is_empty() {
+            self.pieces_cache.set_columns(4);
+            // rows line up with `pieces`, so they need to be in slot order too
+            self.pieces.sort_by_key(|piece| piece.type_val);
+            let pieces = &self.pieces;
+            for piece in pieces {
+                let skills = piece
+                    .skills
+                    .iter()
+                    .map(|skill| format!("{} +{}", skill.skill_name, skill.level))
+                    .collect::<Vec<_>>();
+                self.pieces_cache.push(piece.type_val.to_string());
+                let link = EntryRequest::new(SearchCategory::Armor, piece.id);
+                self.pieces_cache.push_link(piece.name.clone(), link);
+                self.pieces_cache.push(format!(
+                    "{} ({} max)",
+                    piece.defense.base, piece.defense.max
+                ));
+                self.pieces_cache.push(skills.join(", "));
+            }
+        }
+        &self.pieces_cache
+    }
+
+    fn totals_data(&mut self) -> &TableDataModel {
+        macro_rules! add_total_row {
+            ($name:expr, $group:ident, $field:ident) => {
+                let total: i32 = self.pieces.iter().map(|p| p.$group.$field).sum();
+                self.totals_cache.push($name.to_owned());
+                self.totals_cache.push(total.to_string());
+            };
+        }
+        if self.totals_cache.is_empty() {
+            self.totals_cache.set_columns(2);
+            add_total_row!("Defense", defense, base);
+            add_total_row!("Max Defense", defense, max);
+            add_total_row!("Augmented Defense", defense, augmented);
+            add_total_row!("Fire", resistances, fire);
+            add_total_row!("Water", resistances, water);
+            add_total_row!("Ice", resistances, ice);
+            add_total_row!("Thunder", resistances, thunder);
+            add_total_row!("Dragon", resistances, dragon);
+        }
+        &self.totals_cache
+    }
+
+    fn bonus_data(&mut self) -> &TableDataModel {
+        if self.bonus_cache.is_empty() {
+            self.bonus_cache.set_columns(3);
+            if let Some(bonus) = &self.bonus {
+                for rank in &bonus.ranks {
+                    self.bonus_cache.push(format!("{} Pieces", rank.pieces));
+                    let link = EntryRequest::new(SearchCategory::Skills, rank.skill.skill);
+                    self.bonus_cache
+                        .push_link(rank.skill.skill_name.clone(), link);
+                    self.bonus_cache.push(rank.skill.description.clone());
+                }
+            }
+        }
+        &self.bonus_cache
+    }
+}
+
+impl MhwWindowContents for ArmorSetInfo {
+    fn build_window<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        _details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        //=======================================
+        // Name/ID
+        let rarity = self.pieces.iter().map(|p| p.rarity).max().unwrap_or(0);
+        ui.with_font(FONT_IDX_HEADER, || {
+            let imstring = ImString::new(self.name.as_str());
+            ui.text_colored(rarity_color(rarity), &imstring);
+        });
+        ui.with_font(FONT_IDX_MINI, || {
+            let id_string = format!("id: [{}]", self.id);
+            ui.text(id_string.as_str());
+            ui.same_line(0.0);
+            ui.text(format!("{} Rank, {} Pieces", self.rank, self.pieces.len()));
+        });
+
+        //=======================================
+        // Lists section
+        ui.separator();
+        draw_table(ui, "Pieces", self.pieces_data(), event_queue);
+
+        ui.columns(2, im_str!("set_attribs"), true);
+        draw_table(ui, "Totals", self.totals_data(), event_queue);
+
+        ui.next_column();
+        let bonus_title = match &self.bonus {
+            Some(bonus) => format!("Set Bonus: {}", bonus.name),
+            None => "Set Bonus".to_owned(),
+        };
+        draw_table(ui, bonus_title.as_str(), self.bonus_data(), event_queue);
+    }
+}
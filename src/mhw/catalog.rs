@@ -0,0 +1,162 @@
+use super::armor_sets::ArmorSetInfo;
+use super::common::MhwEvent;
+use super::decorations::DecorationInfo;
+use super::item_sources::ItemSource;
+use super::set_search::SetSearchData;
+use super::skills::SkillInfo;
+use super::weapon_tree::WeaponTreeNode;
+use super::weapons::WeaponType;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+//
+// Catalog Collection
+//
+// A whole collection that views need for reference (e.g. every decoration when drawing slots).
+// Each one is fetched on the query worker the first time something asks for it.
+#[derive(Debug, Default)]
+pub enum CatalogCollection<T> {
+    #[default]
+    Unloaded,
+    Loading,
+    Loaded(Vec<T>),
+    Failed,
+}
+
+impl<T> CatalogCollection<T> {
+    // Returns the entries if they are ready, otherwise queues `request` the first time around
+    pub fn get_or_request(
+        &mut self,
+        event_queue: &mut VecDeque<MhwEvent>,
+        request: MhwEvent,
+    ) -> Option<&[T]> {
+        if let CatalogCollection::Unloaded = self {
+            *self = CatalogCollection::Loading;
+            event_queue.push_back(request);
+        }
+        self.get()
+    }
+
+    pub fn get(&self) -> Option<&[T]> {
+        match self {
+            CatalogCollection::Loaded(entries) => Some(entries.as_slice()),
+            _ => None,
+        }
+    }
+
+    // True until the collection has either loaded or failed
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            CatalogCollection::Unloaded | CatalogCollection::Loading
+        )
+    }
+
+    pub fn set(&mut self, entries: Vec<T>) {
+        *self = CatalogCollection::Loaded(entries);
+    }
+
+    pub fn set_failed(&mut self) {
+        *self = CatalogCollection::Failed;
+    }
+
+    // Failed collections stay failed until asked, so the next `get_or_request` fetches it again
+    pub fn retry(&mut self) {
+        if let CatalogCollection::Failed = self {
+            *self = CatalogCollection::Unloaded;
+        }
+    }
+}
+
+//
+// Catalog
+//
+#[derive(Debug, Default)]
+pub struct Catalog {
+    pub decorations: CatalogCollection<DecorationInfo>,
+    pub skills: CatalogCollection<SkillInfo>,
+    pub armor_sets: CatalogCollection<ArmorSetInfo>,
+    pub weapon_trees: BTreeMap<WeaponType, CatalogCollection<WeaponTreeNode>>,
+    pub item_sources: CatalogCollection<ItemSource>,
+    pub set_search_data: Option<Arc<SetSearchData>>, // kept by the first armor set search
+}
+
+impl Catalog {
+    // Call when the data source changes so everything is fetched again from the new one
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    pub fn decoration(&self, id: i32) -> Option<&DecorationInfo> {
+        self.decorations.get()?.iter().find(|deco| deco.id == id)
+    }
+
+    pub fn skill(&self, id: i32) -> Option<&SkillInfo> {
+        self.skills.get()?.iter().find(|skill| skill.id == id)
+    }
+
+    pub fn armor_set(&self, id: i32) -> Option<&ArmorSetInfo> {
+        self.armor_sets.get()?.iter().find(|set| set.id == id)
+    }
+
+    // Everything known to drop or use `item`, if the index is ready
+    pub fn item_sources(
+        &mut self,
+        item: i32,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) -> Option<Vec<&ItemSource>> {
+        let sources = self
+            .item_sources
+            .get_or_request(event_queue, MhwEvent::LoadItemSources)?;
+        Some(
+            sources
+                .iter()
+                .filter(|source| source.item == item)
+                .collect(),
+        )
+    }
+
+    // Trees are loaded one weapon type at a time
+    pub fn weapon_tree(
+        &mut self,
+        weapon_type: WeaponType,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) -> Option<&[WeaponTreeNode]> {
+        self.weapon_trees
+            .entry(weapon_type)
+            .or_default()
+            .get_or_request(event_queue, MhwEvent::LoadWeaponTree(weapon_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_collections_load_again_on_retry() {
+        let mut events = VecDeque::new();
+        let mut collection = CatalogCollection::<i32>::default();
+        assert!(collection
+            .get_or_request(&mut events, MhwEvent::LoadItemSources)
+            .is_none());
+        assert!(collection
+            .get_or_request(&mut events, MhwEvent::LoadItemSources)
+            .is_none());
+        assert_eq!(events.len(), 1);
+
+        collection.set_failed();
+        assert!(!collection.is_pending());
+        collection.get_or_request(&mut events, MhwEvent::LoadItemSources);
+        assert_eq!(events.len(), 1);
+
+        collection.retry();
+        assert!(collection.is_pending());
+        collection.get_or_request(&mut events, MhwEvent::LoadItemSources);
+        assert_eq!(events.len(), 2);
+
+        collection.set(vec![1, 2]);
+        collection.retry();
+        assert_eq!(collection.get(), Some(&[1, 2][..]));
+    }
+}
@@ -0,0 +1,132 @@
+use super::common::{
+    fonts::*, rarity::*, CraftingCost, GuiDetails, MhwEvent, MhwWindowContents, SkillRank,
+};
+use super::entry_display::EntryRequest;
+use super::search::SearchCategory;
+use super::wishlist::WishlistItem;
+use crate::widgets::table_view::*;
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CharmCraftingInfo {
+    pub craftable: bool, // only the first rank is crafted, the rest are upgrades
+    pub materials: Vec<CraftingCost>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CharmRank {
+    pub name: String,
+    pub level: i32,
+    pub rarity: u32,
+    pub skills: Vec<SkillRank>,
+    pub crafting: CharmCraftingInfo,
+}
+
+impl CharmRank {
+    // The rank is only named on its first row, so the rows read as one group
+    fn materials_table(&self) -> SimpleTableDataModel {
+        let mut table = SimpleTableDataModel::new(3);
+        let label = if self.crafting.craftable {
+            format!("{} Crafting", self.name)
+        } else {
+            format!("{} Upgrade", self.name)
+        };
+        for (idx, cost) in self.crafting.materials.iter().enumerate() {
+            table.push(if idx == 0 {
+                label.clone()
+            } else {
+                String::new()
+            });
+            let link = EntryRequest::new(SearchCategory::Items, cost.item.id);
+            table.push_link(cost.item.name.clone(), link);
+            table.push(cost.quantity.to_string());
+        }
+        table
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CharmInfo {
+    pub id: i32,
+    pub name: String,
+    pub ranks: Vec<CharmRank>,
+
+    // internal details
+    #[serde(skip)]
+    ranks_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    materials_cache: CompoundTableDataModel<SimpleTableDataModel>,
+}
+
+impl CharmInfo {
+    fn ranks_data(&mut self) -> &TableDataModel {
+        if self.ranks_cache.is_empty() {
+            self.ranks_cache.set_columns(3);
+            let ranks = &self.ranks;
+            for rank in ranks {
+                let skills = rank
+                    .skills
+                    .iter()
+                    .map(|skill| format!("{} +{}", skill.skill_name, skill.level))
+                    .collect::<Vec<_>>();
+                self.ranks_cache.push(format!("Lv {}", rank.level));
+                self.ranks_cache.push(rank.name.clone());
+                self.ranks_cache.push(skills.join(", "));
+            }
+        }
+        &self.ranks_cache
+    }
+
+    fn materials_data(&mut self) -> &TableDataModel {
+        if self.materials_cache.is_empty() {
+            for rank in &self.ranks {
+                self.materials_cache.push(rank.materials_table());
+            }
+        }
+        &self.materials_cache
+    }
+}
+
+impl MhwWindowContents for CharmInfo {
+    fn build_window<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        _details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        //=======================================
+        // Name/ID
+        let rarity = self.ranks.last().map_or(0, |rank| rank.rarity);
+        ui.with_font(FONT_IDX_HEADER, || {
+            let imstring = ImString::new(self.name.as_str());
+            ui.text_colored(rarity_color(rarity), &imstring);
+        });
+        ui.with_font(FONT_IDX_MINI, || {
+            let id_string = format!("id: [{}]", self.id);
+            ui.text(id_string.as_str());
+            ui.same_line(0.0);
+            ui.text(format!("{} Ranks", self.ranks.len()));
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Equip")) {
+                let request = EntryRequest::new(SearchCategory::Charms, self.id);
+                event_queue.push_back(MhwEvent::Equip(request));
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Wishlist")) {
+                let item = WishlistItem::from_charm(self);
+                event_queue.push_back(MhwEvent::AddToWishlist(item));
+            }
+        });
+
+        //=======================================
+        // Lists section
+        ui.separator();
+        draw_table(ui, "Ranks", self.ranks_data(), event_queue);
+        draw_table(ui, "Materials", self.materials_data(), event_queue);
+    }
+}
@@ -1,10 +1,30 @@
-use super::entry_display::EntryDisplayState;
+use super::armor_sets::ArmorSetInfo;
+use super::catalog::Catalog;
+use super::data_source::*;
+use super::database::{LocalDatabase, DEFAULT_DATABASE_DIR};
+use super::decorations::DecorationInfo;
+use super::entry_display::{fetch_entry, EntryDisplayState, EntryRequest};
+use super::error_panel::ErrorPanel;
+use super::history::NavigationHistory;
+use super::item_sources::{load_item_sources, ItemSource};
 use super::items::*;
-use super::search::SearchState;
+use super::loadout::Loadout;
+use super::monsters::KiranicoDetails;
+use super::query::{MHWQueryError, QueryInfo};
+use super::query_worker::{QueryWorker, RequestTracker};
+use super::scraping::{load_monster, ScrapedMonster};
+use super::search::{SearchCategory, SearchResults, SearchState};
+use super::set_search::{SetSearchData, SetSearchRequest, SetSearchResults, SetSearchState};
+use super::skills::SkillInfo;
+use super::weapon_tree::{load_weapon_tree, WeaponTreeNode};
+use super::weapons::WeaponType;
+use super::wishlist::{Wishlist, WishlistItem, DEFAULT_OWNED_PATH};
 use imgui::*;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt::{self, Debug, Display};
+use std::path::Path;
+use std::sync::Arc;
 
 pub mod fonts {
     pub const FONT_IDX_NORMAL: usize = 1;
@@ -59,6 +79,9 @@ use fonts::*;
 pub struct GuiDetails {
     pub next_start_pos: (f32, f32),
     pub draw_filter_window: bool,
+    pub draw_set_search_window: bool,
+    pub draw_wishlist_window: bool,
+    pub catalog: Catalog,
 }
 
 pub trait MhwGui {
@@ -79,7 +102,7 @@ pub trait MhwWindowContents {
     );
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 pub enum Element {
     Fire,
@@ -91,6 +114,7 @@ pub enum Element {
     Poison,
     Sleep,
     Paralysis,
+    Stun, // only shows up in monster weaknesses
 }
 
 impl Display for Element {
@@ -99,12 +123,30 @@ impl Display for Element {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CraftingCost {
     pub quantity: i32,
     pub item: ItemInfo,
 }
 
+// Adds up the quantities of the same item, keeping the order items are first seen in
+pub fn merge_costs<'a, I>(costs: I) -> Vec<CraftingCost>
+where
+    I: IntoIterator<Item = &'a CraftingCost>,
+{
+    let mut merged: Vec<CraftingCost> = vec![];
+    for cost in costs {
+        match merged
+            .iter_mut()
+            .find(|total| total.item.id == cost.item.id)
+        {
+            Some(total) => total.quantity += cost.quantity,
+            None => merged.push(cost.clone()),
+        }
+    }
+    merged
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Slot {
     pub rank: i32,
@@ -142,9 +184,104 @@ pub struct SkillRankModifiers {
     pub resist_dragon: i32,
 }
 
+impl SkillRankModifiers {
+    // Lists every modifier that actually does something, e.g. "Attack +9, Affinity +5%"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = vec![];
+        macro_rules! try_add_modifier {
+            ($name:expr, $field:ident) => {
+                if self.$field != 0 {
+                    parts.push(format!("{} {:+}", $name, self.$field));
+                }
+            };
+        }
+        try_add_modifier!("Attack", attack);
+        if self.affinity != 0.0 {
+            parts.push(format!("Affinity {:+}%", self.affinity));
+        }
+        try_add_modifier!("Fire Damage", damage_fire);
+        try_add_modifier!("Water Damage", damage_water);
+        try_add_modifier!("Ice Damage", damage_ice);
+        try_add_modifier!("Thunder Damage", damage_thunder);
+        try_add_modifier!("Dragon Damage", damage_dragon);
+        try_add_modifier!("Defense", defense);
+        try_add_modifier!("Health", health);
+        try_add_modifier!("Sharpness", sharpness_bonus);
+        try_add_modifier!("All Resistances", resist_all);
+        try_add_modifier!("Fire Res", resist_fire);
+        try_add_modifier!("Water Res", resist_water);
+        try_add_modifier!("Ice Res", resist_ice);
+        try_add_modifier!("Thunder Res", resist_thunder);
+        try_add_modifier!("Dragon Res", resist_dragon);
+
+        parts.join(", ")
+    }
+
+    // Adds another skill's modifiers on top of these
+    pub fn add(&mut self, other: &SkillRankModifiers) {
+        macro_rules! add_modifiers {
+            ($($field:ident),*) => {
+                $(self.$field += other.$field;)*
+            };
+        }
+        add_modifiers!(
+            affinity,
+            attack,
+            damage_fire,
+            damage_water,
+            damage_ice,
+            damage_thunder,
+            damage_dragon,
+            defense,
+            health,
+            sharpness_bonus,
+            resist_all,
+            resist_fire,
+            resist_water,
+            resist_ice,
+            resist_thunder,
+            resist_dragon
+        );
+    }
+}
+
 #[derive(Debug)]
 pub enum MhwEvent {
-    ShowState(EntryDisplayState),
+    RequestSearch(QueryInfo),
+    SearchLoaded(usize, Result<Vec<SearchResults>, MHWQueryError>),
+    RequestEntry(EntryRequest),
+    NavigateBack,
+    NavigateForward,
+    NavigateTo(usize), // index into the navigation history
+    Equip(EntryRequest),
+    EquipLoaded(Result<EntryDisplayState, MHWQueryError>),
+    EntryLoaded(
+        usize,
+        EntryRequest,
+        Result<EntryDisplayState, MHWQueryError>,
+    ),
+    SyncDatabase,
+    DatabaseSynced(Result<LocalDatabase, MHWQueryError>),
+    ClearCache,
+    LoadDecorations,
+    DecorationsLoaded(Result<Vec<DecorationInfo>, MHWQueryError>),
+    LoadSkills,
+    SkillsLoaded(Result<Vec<SkillInfo>, MHWQueryError>),
+    LoadArmorSets,
+    ArmorSetsLoaded(Result<Vec<ArmorSetInfo>, MHWQueryError>),
+    LoadWeaponTree(WeaponType),
+    WeaponTreeLoaded(WeaponType, Result<Vec<WeaponTreeNode>, MHWQueryError>),
+    LoadKiranico(i32, String), // monster id and name
+    KiranicoLoaded(i32, Result<ScrapedMonster, MHWQueryError>),
+    LoadItemSources,
+    ItemSourcesLoaded(Result<Vec<ItemSource>, MHWQueryError>),
+    AddToWishlist(WishlistItem),
+    SearchSets(SetSearchRequest),
+    SetsFound(
+        usize,
+        Result<(Arc<SetSearchData>, SetSearchResults), MHWQueryError>,
+    ),
+    ShowError(&'static str, MHWQueryError), // what was being done, and why it failed
 }
 
 #[derive(Debug)]
@@ -152,8 +289,18 @@ pub struct AppState {
     quit_requested: bool,
     search_state: SearchState,
     entry_display_state: EntryDisplayState,
+    history: NavigationHistory,
+    loadout: Loadout,
+    set_search: SetSearchState,
+    wishlist: Wishlist,
     event_list: VecDeque<MhwEvent>,
     gui_details: GuiDetails,
+    error_panel: ErrorPanel,
+    data_source: Arc<dyn DataSource>,
+    worker: QueryWorker,
+    search_requests: RequestTracker,
+    entry_requests: RequestTracker,
+    set_search_requests: RequestTracker,
 }
 
 impl AppState {
@@ -161,13 +308,285 @@ impl AppState {
         self.quit_requested
     }
 
+    // Shows the entry as loading and fetches it on the worker
+    fn load_entry(&mut self, request: EntryRequest) {
+        self.entry_display_state = EntryDisplayState::Loading(request);
+
+        let ticket = self.entry_requests.next();
+        let tracker = self.entry_requests.clone();
+        let source = Arc::clone(&self.data_source);
+        self.worker.spawn(move || {
+            // skip selections the user has already moved past
+            if !tracker.is_current(ticket) {
+                return None;
+            }
+            let found = fetch_entry(source.as_ref(), &request);
+            Some(MhwEvent::EntryLoaded(ticket, request, found))
+        });
+    }
+
     pub fn process_events(&mut self) {
-        for evt in self.event_list.drain(..) {
+        // pick up anything the worker finished since last frame
+        self.event_list.extend(self.worker.poll());
+        for e in self.data_source.take_cache_errors() {
+            self.error_panel.push("Caching a response failed", e);
+        }
+
+        let events = self.event_list.drain(..).collect::<Vec<_>>();
+        for evt in events {
             match evt {
-                MhwEvent::ShowState(state) => {
-                    // Set the current display state to the one we just loaded
-                    self.entry_display_state = state;
+                MhwEvent::RequestSearch(query) => {
+                    let ticket = self.search_requests.next();
+                    let tracker = self.search_requests.clone();
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        if !tracker.is_current(ticket) {
+                            return None;
+                        }
+                        let found = query.execute(source.as_ref());
+                        Some(MhwEvent::SearchLoaded(ticket, found))
+                    });
+                }
+                MhwEvent::SearchLoaded(ticket, found) => {
+                    if self.search_requests.is_current(ticket) {
+                        match found {
+                            Ok(results) => self.search_state.set_results(results),
+                            Err(e) => {
+                                self.search_state.set_results(vec![]);
+                                self.error_panel.push("Search failed", e);
+                            }
+                        }
+                    }
+                }
+                MhwEvent::RequestEntry(request) => {
+                    self.history.visit(request);
+                    self.load_entry(request);
+                }
+                MhwEvent::NavigateBack => {
+                    if let Some(request) = self.history.back() {
+                        self.load_entry(request);
+                    }
+                }
+                MhwEvent::NavigateForward => {
+                    if let Some(request) = self.history.forward() {
+                        self.load_entry(request);
+                    }
+                }
+                MhwEvent::NavigateTo(index) => {
+                    if let Some(request) = self.history.go_to(index) {
+                        self.load_entry(request);
+                    }
+                }
+                MhwEvent::EntryLoaded(ticket, request, found) => {
+                    if self.entry_requests.is_current(ticket) {
+                        self.entry_display_state = match found {
+                            Ok(state) => {
+                                if let Some(name) = state.name() {
+                                    self.history.set_name(request, name);
+                                }
+                                state
+                            }
+                            Err(e) => {
+                                let message = e.to_string();
+                                self.error_panel.push(
+                                    format!("Loading {} [{}]", request.category, request.id)
+                                        .as_str(),
+                                    e,
+                                );
+                                EntryDisplayState::Failed(request, message)
+                            }
+                        };
+                    }
+                }
+                MhwEvent::Equip(request) => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        let found = fetch_entry(source.as_ref(), &request);
+                        Some(MhwEvent::EquipLoaded(found))
+                    });
+                }
+                MhwEvent::EquipLoaded(found) => match found {
+                    Ok(state) => self.loadout.equip(state),
+                    Err(e) => self.error_panel.push("Equipping failed", e),
+                },
+                MhwEvent::SyncDatabase => {
+                    let api = HttpDataSource::default();
+                    let base_url = api.base_url().to_owned();
+                    self.worker.spawn(move || {
+                        let dir = Path::new(DEFAULT_DATABASE_DIR);
+                        let synced = LocalDatabase::sync(dir, base_url.as_str());
+                        Some(MhwEvent::DatabaseSynced(synced))
+                    });
+                }
+                MhwEvent::DatabaseSynced(synced) => match synced {
+                    Ok(db) => {
+                        self.data_source = Arc::new(SnapshotDataSource::from(db));
+                        self.gui_details.catalog.clear();
+                    }
+                    Err(e) => self.error_panel.push("Offline data sync failed", e),
+                },
+                MhwEvent::ClearCache => {
+                    if let Err(e) = self.data_source.clear_cache() {
+                        self.error_panel.push("Clearing the cache failed", e);
+                    }
+                }
+                MhwEvent::LoadDecorations => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        let query = QueryInfo::find_category(SearchCategory::Decorations);
+                        Some(MhwEvent::DecorationsLoaded(query.execute(source.as_ref())))
+                    });
+                }
+                MhwEvent::DecorationsLoaded(found) => {
+                    match found {
+                        Ok(decorations) => self.gui_details.catalog.decorations.set(decorations),
+                        Err(e) => {
+                            self.gui_details.catalog.decorations.set_failed();
+                            self.error_panel.push("Loading decorations failed", e);
+                        }
+                    }
+                    self.loadout.catalog_changed();
+                }
+                MhwEvent::LoadSkills => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        let query = QueryInfo::find_category(SearchCategory::Skills);
+                        Some(MhwEvent::SkillsLoaded(query.execute(source.as_ref())))
+                    });
+                }
+                MhwEvent::SkillsLoaded(found) => {
+                    match found {
+                        Ok(skills) => self.gui_details.catalog.skills.set(skills),
+                        Err(e) => {
+                            self.gui_details.catalog.skills.set_failed();
+                            self.error_panel.push("Loading skills failed", e);
+                        }
+                    }
+                    self.loadout.catalog_changed();
+                }
+                MhwEvent::LoadArmorSets => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        let query = QueryInfo::find_category(SearchCategory::ArmorSets);
+                        Some(MhwEvent::ArmorSetsLoaded(query.execute(source.as_ref())))
+                    });
+                }
+                MhwEvent::ArmorSetsLoaded(found) => {
+                    match found {
+                        Ok(sets) => self.gui_details.catalog.armor_sets.set(sets),
+                        Err(e) => {
+                            self.gui_details.catalog.armor_sets.set_failed();
+                            self.error_panel.push("Loading armor sets failed", e);
+                        }
+                    }
+                    self.loadout.catalog_changed();
+                }
+                MhwEvent::LoadWeaponTree(weapon_type) => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        let found = load_weapon_tree(source.as_ref(), weapon_type);
+                        Some(MhwEvent::WeaponTreeLoaded(weapon_type, found))
+                    });
+                }
+                MhwEvent::WeaponTreeLoaded(weapon_type, found) => {
+                    let tree = self
+                        .gui_details
+                        .catalog
+                        .weapon_trees
+                        .entry(weapon_type)
+                        .or_default();
+                    match found {
+                        Ok(nodes) => tree.set(nodes),
+                        Err(e) => {
+                            tree.set_failed();
+                            self.error_panel.push("Loading the weapon tree failed", e);
+                        }
+                    }
+                }
+                MhwEvent::LoadKiranico(id, name) => {
+                    self.worker.spawn(move || {
+                        Some(MhwEvent::KiranicoLoaded(id, load_monster(name.as_str())))
+                    });
+                }
+                MhwEvent::KiranicoLoaded(id, found) => {
+                    // the monster may have been navigated away from in the meantime
+                    let shown = match &mut self.entry_display_state {
+                        EntryDisplayState::Monster(monster) if monster.id == id => Some(monster),
+                        _ => None,
+                    };
+                    match found {
+                        Ok(scraped) => {
+                            if let Some(monster) = shown {
+                                monster.kiranico = KiranicoDetails::Loaded(scraped);
+                            }
+                        }
+                        Err(e) => {
+                            if let Some(monster) = shown {
+                                monster.kiranico = KiranicoDetails::Failed;
+                            }
+                            self.error_panel.push("Loading kiranico data failed", e);
+                        }
+                    }
+                }
+                MhwEvent::LoadItemSources => {
+                    let source = Arc::clone(&self.data_source);
+                    self.worker.spawn(move || {
+                        Some(MhwEvent::ItemSourcesLoaded(load_item_sources(
+                            source.as_ref(),
+                        )))
+                    });
+                }
+                MhwEvent::ItemSourcesLoaded(found) => match found {
+                    Ok(sources) => self.gui_details.catalog.item_sources.set(sources),
+                    Err(e) => {
+                        self.gui_details.catalog.item_sources.set_failed();
+                        self.error_panel.push("Loading item sources failed", e);
+                    }
+                },
+                MhwEvent::AddToWishlist(item) => {
+                    self.wishlist.add(item);
+                    self.gui_details.draw_wishlist_window = true;
+                }
+                MhwEvent::SearchSets(mut request) => {
+                    if let Some(weapon) = &self.loadout.weapon {
+                        request.weapon_slots = weapon.item.slots.iter().map(|s| s.rank).collect();
+                    }
+
+                    let ticket = self.set_search_requests.next();
+                    let tracker = self.set_search_requests.clone();
+                    let source = Arc::clone(&self.data_source);
+                    let loaded = self.gui_details.catalog.set_search_data.clone();
+                    self.worker.spawn(move || {
+                        if !tracker.is_current(ticket) {
+                            return None;
+                        }
+                        // every armor piece is needed, so this is only fetched once
+                        let data = match loaded {
+                            Some(data) => data,
+                            None => match SetSearchData::load(source.as_ref()) {
+                                Ok(data) => Arc::new(data),
+                                Err(e) => return Some(MhwEvent::SetsFound(ticket, Err(e))),
+                            },
+                        };
+                        let results = data.solve(&request);
+                        Some(MhwEvent::SetsFound(ticket, Ok((data, results))))
+                    });
+                }
+                MhwEvent::SetsFound(ticket, found) => {
+                    if self.set_search_requests.is_current(ticket) {
+                        match found {
+                            Ok((data, results)) => {
+                                self.gui_details.catalog.set_search_data = Some(data);
+                                self.set_search.set_results(Some(results));
+                            }
+                            Err(e) => {
+                                self.set_search.set_results(None);
+                                self.error_panel.push("Armor set search failed", e);
+                            }
+                        }
+                    }
                 }
+                MhwEvent::ShowError(context, e) => self.error_panel.push(context, e),
             }
         }
     }
@@ -180,41 +599,116 @@ impl AppState {
                 self.gui_details.next_start_pos.1 = ui.get_window_size().1;
                 ui.menu(im_str!("File")).build(|| {
                     ui.with_font(1, || {
+                        if ui.menu_item(im_str!("Sync Offline Data")).build() {
+                            self.event_list.push_back(MhwEvent::SyncDatabase);
+                        }
+                        if ui.menu_item(im_str!("Clear Cache")).build() {
+                            self.event_list.push_back(MhwEvent::ClearCache);
+                        }
                         if ui.menu_item(im_str!("Quit")).build() {
                             self.quit_requested = true
                         }
                     });
                 });
+                ui.menu(im_str!("Tools")).build(|| {
+                    ui.with_font(1, || {
+                        if ui.menu_item(im_str!("Armor Set Search")).build() {
+                            self.gui_details.draw_set_search_window = true;
+                        }
+                        if ui.menu_item(im_str!("Wishlist")).build() {
+                            self.gui_details.draw_wishlist_window = true;
+                        }
+                    });
+                });
             });
         });
 
         ui.with_font(FONT_IDX_NORMAL, || {
             self.search_state
-                .layout(&ui, &mut self.gui_details, &mut self.event_list);
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
+            self.loadout
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
+            self.history
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
             self.entry_display_state
-                .layout(&ui, &mut self.gui_details, &mut self.event_list);
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
             if self.gui_details.draw_filter_window {
                 self.search_state.layout_filter_window(
-                    &ui,
+                    ui,
                     &mut self.gui_details,
                     &mut self.event_list,
                 );
             }
+            self.set_search
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
+            self.wishlist
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
+            self.error_panel
+                .layout(ui, &mut self.gui_details, &mut self.event_list);
         });
     }
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        let (data_source, snapshot_error) = default_data_source();
+        let mut error_panel = ErrorPanel::default();
+        if let Some(e) = snapshot_error {
+            error_panel.push("Loading offline data failed", e);
+        }
+        let (wishlist, wishlist_error) = Wishlist::load(Path::new(DEFAULT_OWNED_PATH));
+        if let Some(e) = wishlist_error {
+            error_panel.push("Loading owned items failed", e);
+        }
+
         Self {
             quit_requested: false,
             search_state: Default::default(),
             entry_display_state: Default::default(),
+            history: Default::default(),
+            loadout: Default::default(),
+            set_search: Default::default(),
+            wishlist,
             event_list: Default::default(),
             gui_details: GuiDetails {
                 next_start_pos: (0.0, 0.0),
                 draw_filter_window: false,
+                draw_set_search_window: false,
+                draw_wishlist_window: false,
+                catalog: Default::default(),
             },
+            error_panel,
+            data_source,
+            worker: Default::default(),
+            search_requests: Default::default(),
+            entry_requests: Default::default(),
+            set_search_requests: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(item: i32, quantity: i32) -> CraftingCost {
+        let mut info = ItemInfo::default();
+        info.id = item;
+        info.name = format!("Item {}", item);
+        CraftingCost {
+            quantity,
+            item: info,
         }
     }
+
+    #[test]
+    fn costs_merge_by_item_id() {
+        let costs = vec![cost(7, 2), cost(3, 1), cost(7, 3), cost(5, 4), cost(3, 1)];
+        let merged = merge_costs(&costs)
+            .into_iter()
+            .map(|cost| (cost.item.id, cost.quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(merged, vec![(7, 5), (3, 2), (5, 4)]);
+        assert!(merge_costs(&[]).is_empty());
+    }
 }
@@ -0,0 +1,256 @@
+use super::common::{Element, SkillRankModifiers};
+use super::weapons::{WeaponInfo, WeaponSharpness, WeaponType};
+use std::fmt::{self, Debug, Display};
+
+// Turns a weapon's listed numbers into what it actually averages per hit, before motion values and
+// hitzones. Raw is the true raw mhw-db lists as `attack.raw`; element stays in the listed units.
+
+const CRIT_BOOST_MULTIPLIERS: [f32; 4] = [1.25, 1.30, 1.35, 1.40]; // by Critical Boost level
+const NEGATIVE_CRIT_MULTIPLIER: f32 = 0.75;
+
+//
+// Sharpness
+//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sharpness {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    White,
+}
+
+impl Sharpness {
+    pub fn raw_multiplier(self) -> f32 {
+        match self {
+            Sharpness::Red => 0.5,
+            Sharpness::Orange => 0.75,
+            Sharpness::Yellow => 1.0,
+            Sharpness::Green => 1.05,
+            Sharpness::Blue => 1.2,
+            Sharpness::White => 1.32,
+        }
+    }
+
+    pub fn element_multiplier(self) -> f32 {
+        match self {
+            Sharpness::Red => 0.25,
+            Sharpness::Orange => 0.5,
+            Sharpness::Yellow => 0.75,
+            Sharpness::Green => 1.0,
+            Sharpness::Blue => 1.0625,
+            Sharpness::White => 1.15,
+        }
+    }
+}
+
+impl Display for Sharpness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl WeaponSharpness {
+    // The segment a fresh sharpen starts on, i.e. the highest one with any length
+    pub fn top_segment(&self) -> Option<Sharpness> {
+        let segments = [
+            (self.white, Sharpness::White),
+            (self.blue, Sharpness::Blue),
+            (self.green, Sharpness::Green),
+            (self.yellow, Sharpness::Yellow),
+            (self.orange, Sharpness::Orange),
+            (self.red, Sharpness::Red),
+        ];
+        segments
+            .iter()
+            .find(|(length, _)| *length > 0.0)
+            .map(|(_, sharpness)| *sharpness)
+    }
+}
+
+// `durability` holds one bar per handicraft level; levels past the last bar don't add anything
+pub fn sharpness_at(durability: &[WeaponSharpness], handicraft: i32) -> Option<Sharpness> {
+    let level = (handicraft.max(0) as usize).min(durability.len().checked_sub(1)?);
+    durability[level].top_segment()
+}
+
+// How much Critical Element multiplies element on a crit, by weapon class
+pub fn crit_element_multiplier(weapon_type: &WeaponType) -> f32 {
+    match weapon_type {
+        WeaponType::GreatSword => 1.2,
+        WeaponType::DualBlades | WeaponType::Bow => 1.35,
+        WeaponType::LightBowgun | WeaponType::HeavyBowgun => 1.3,
+        _ => 1.25,
+    }
+}
+
+// Affinity is a percent; anything past +/-100 does nothing
+fn crit_chance(affinity: f32) -> f32 {
+    (affinity / 100.0).clamp(-1.0, 1.0)
+}
+
+pub fn effective_raw(raw: f32, affinity: f32, sharpness: f32, critical_boost: i32) -> f32 {
+    let chance = crit_chance(affinity);
+    let crit_multiplier = if chance >= 0.0 {
+        let level = (critical_boost.max(0) as usize).min(CRIT_BOOST_MULTIPLIERS.len() - 1);
+        CRIT_BOOST_MULTIPLIERS[level]
+    } else {
+        NEGATIVE_CRIT_MULTIPLIER
+    };
+    raw * sharpness * (1.0 + chance.abs() * (crit_multiplier - 1.0))
+}
+
+// `crit_multiplier` is None without Critical Element; negative affinity never lowers element
+pub fn effective_element(
+    element: f32,
+    affinity: f32,
+    sharpness: f32,
+    crit_multiplier: Option<f32>,
+) -> f32 {
+    let chance = crit_chance(affinity).max(0.0);
+    let crit_multiplier = crit_multiplier.unwrap_or(1.0);
+    element * sharpness * (1.0 + chance * (crit_multiplier - 1.0))
+}
+
+//
+// Damage Skills
+//
+// The skills that change the math rather than adding flat modifiers
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DamageSkills {
+    pub handicraft: i32,
+    pub critical_boost: i32,
+    pub critical_element: bool,
+}
+
+//
+// Effective Damage
+//
+#[derive(Debug)]
+pub struct EffectiveDamage {
+    pub sharpness: Option<Sharpness>, // None for bows and bowguns
+    pub affinity: f32,
+    pub raw: f32,
+    pub elements: Vec<(Element, f32)>, // hidden elements are left out
+}
+
+fn element_bonus(element: Element, modifiers: &SkillRankModifiers) -> i32 {
+    match element {
+        Element::Fire => modifiers.damage_fire,
+        Element::Water => modifiers.damage_water,
+        Element::Ice => modifiers.damage_ice,
+        Element::Thunder => modifiers.damage_thunder,
+        Element::Dragon => modifiers.damage_dragon,
+        _ => 0,
+    }
+}
+
+pub fn weapon_damage(
+    weapon: &WeaponInfo,
+    modifiers: &SkillRankModifiers,
+    skills: &DamageSkills,
+) -> EffectiveDamage {
+    let sharpness = sharpness_at(&weapon.durability, skills.handicraft);
+    let raw_sharpness = sharpness.map_or(1.0, Sharpness::raw_multiplier);
+    let element_sharpness = sharpness.map_or(1.0, Sharpness::element_multiplier);
+    let affinity = weapon.attributes.affinity.unwrap_or(0) as f32 + modifiers.affinity;
+
+    let raw = (weapon.attack.raw + modifiers.attack) as f32;
+    let crit_element = if skills.critical_element {
+        Some(crit_element_multiplier(&weapon.type_val))
+    } else {
+        None
+    };
+    let elements = weapon
+        .elements
+        .iter()
+        .filter(|elem| !elem.hidden)
+        .map(|elem| {
+            let element = (elem.damage + element_bonus(elem.elememt, modifiers)) as f32;
+            let value = effective_element(element, affinity, element_sharpness, crit_element);
+            (elem.elememt, value)
+        })
+        .collect();
+
+    EffectiveDamage {
+        sharpness,
+        affinity,
+        raw: effective_raw(raw, affinity, raw_sharpness, skills.critical_boost),
+        elements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn bar(
+        red: f32,
+        orange: f32,
+        yellow: f32,
+        green: f32,
+        blue: f32,
+        white: f32,
+    ) -> WeaponSharpness {
+        WeaponSharpness {
+            red,
+            orange,
+            yellow,
+            green,
+            blue,
+            white,
+        }
+    }
+
+    #[test]
+    fn raw_without_affinity_only_uses_sharpness() {
+        assert_close(effective_raw(200.0, 0.0, 1.0, 0), 200.0);
+        assert_close(effective_raw(200.0, 0.0, 1.2, 3), 240.0);
+    }
+
+    #[test]
+    fn raw_with_affinity_and_critical_boost() {
+        // 100% affinity on white: 200 * 1.32 * 1.25
+        assert_close(effective_raw(200.0, 100.0, 1.32, 0), 330.0);
+        // 50% affinity with Critical Boost 3: 200 * 1.32 * (1 + 0.5 * 0.4)
+        assert_close(effective_raw(200.0, 50.0, 1.32, 3), 316.8);
+        // affinity past 100% is wasted
+        assert_close(effective_raw(200.0, 130.0, 1.0, 0), 250.0);
+    }
+
+    #[test]
+    fn negative_affinity_lowers_raw() {
+        // -20% affinity: 200 * (1 - 0.2 * 0.25); Critical Boost doesn't help
+        assert_close(effective_raw(200.0, -20.0, 1.0, 3), 190.0);
+    }
+
+    #[test]
+    fn element_with_critical_element() {
+        // 300 element on white, 40% affinity, 1.35 crit element: 300 * 1.15 * 1.14
+        assert_close(effective_element(300.0, 40.0, 1.15, Some(1.35)), 393.3);
+        assert_close(effective_element(300.0, 40.0, 1.15, None), 345.0);
+        assert_close(effective_element(300.0, -20.0, 1.15, Some(1.35)), 345.0);
+    }
+
+    #[test]
+    fn sharpness_uses_handicraft_level() {
+        let durability = vec![
+            bar(90.0, 50.0, 50.0, 80.0, 30.0, 0.0),
+            bar(90.0, 50.0, 50.0, 80.0, 30.0, 10.0),
+        ];
+        assert_eq!(sharpness_at(&durability, 0), Some(Sharpness::Blue));
+        assert_eq!(sharpness_at(&durability, 1), Some(Sharpness::White));
+        assert_eq!(sharpness_at(&durability, 5), Some(Sharpness::White));
+        assert_eq!(sharpness_at(&[], 2), None);
+    }
+}
@@ -0,0 +1,382 @@
+use super::database::{LocalDatabase, DEFAULT_DATABASE_DIR};
+use super::query::{MHWQueryError, QueryInfo};
+use super::query_cache::*;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER};
+use reqwest::{Client, StatusCode, Url};
+use serde_json::{self, Value};
+use serde_path_to_error;
+use std::env;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_API_URL: &str = "https://mhw-db.com";
+
+// Set this to point the planner at a self-hosted mirror of the API
+pub const API_URL_VAR: &str = "MHW_DB_URL";
+
+//
+// Data Source
+//
+pub trait DataSource: Debug + Send + Sync {
+    // Returns the JSON array of every entry that matches the query
+    fn fetch(&self, query: &QueryInfo) -> Result<Value, MHWQueryError>;
+
+    // Throws away any responses the source has kept around
+    fn clear_cache(&self) -> Result<(), MHWQueryError> {
+        Ok(())
+    }
+
+    // Responses that were fetched fine but couldn't be kept, since the last call
+    fn take_cache_errors(&self) -> Vec<MHWQueryError> {
+        vec![]
+    }
+}
+
+// Prefers the offline snapshot if one has been synced, falling back to the API.
+// Also returns why the snapshot couldn't be used, if there is one that failed to load.
+pub fn default_data_source() -> (Arc<dyn DataSource>, Option<MHWQueryError>) {
+    match SnapshotDataSource::load(Path::new(DEFAULT_DATABASE_DIR)) {
+        Ok(snapshot) => {
+            if snapshot.is_empty() {
+                (Arc::new(HttpDataSource::default()), None)
+            } else {
+                (Arc::new(snapshot), None)
+            }
+        }
+        Err(e) => (Arc::new(HttpDataSource::default()), Some(e)),
+    }
+}
+
+//
+// HTTP Data Source
+//
+#[derive(Debug)]
+pub struct HttpDataSource {
+    base_url: String,
+    cache: Option<QueryCache>,
+    cache_errors: Mutex<Vec<MHWQueryError>>,
+}
+
+impl HttpDataSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            cache: None,
+            cache_errors: Default::default(),
+        }
+    }
+
+    pub fn with_cache(mut self, cache: QueryCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    // A response that can't be cached is still worth returning, so the error is kept for later
+    fn store(&self, cache: &QueryCache, entry: &CacheEntry) {
+        if let Err(e) = cache.store(entry) {
+            if let Ok(mut errors) = self.cache_errors.lock() {
+                errors.push(e);
+            }
+        }
+    }
+
+    fn parse_body(body: &str) -> Result<Value, MHWQueryError> {
+        let mut deserializer = serde_json::Deserializer::from_str(body);
+        match serde_path_to_error::deserialize(&mut deserializer) {
+            Ok(r) => Ok(r),
+            Err(e) => {
+                let path = e.path().to_string();
+                Err(MHWQueryError::from_decode(path, e.into_inner()))
+            }
+        }
+    }
+}
+
+impl Default for HttpDataSource {
+    fn default() -> Self {
+        let base_url = match env::var(API_URL_VAR) {
+            Ok(url) => url,
+            Err(_) => DEFAULT_API_URL.to_owned(),
+        };
+        let ttl_secs = match env::var(CACHE_TTL_VAR) {
+            Ok(secs) => secs.parse::<u64>().unwrap_or(DEFAULT_CACHE_TTL_SECS),
+            Err(_) => DEFAULT_CACHE_TTL_SECS,
+        };
+
+        HttpDataSource::new(base_url.as_str()).with_cache(QueryCache::new(
+            Path::new(DEFAULT_CACHE_DIR),
+            Duration::from_secs(ttl_secs),
+        ))
+    }
+}
+
+impl DataSource for HttpDataSource {
+    fn fetch(&self, query: &QueryInfo) -> Result<Value, MHWQueryError> {
+        let url_string = query.url_string(self.base_url.as_str());
+        let url = match Url::parse(url_string.as_str()) {
+            Ok(url) => url,
+            Err(e) => {
+                return Err(MHWQueryError::Internal(format!(
+                    "Invalid query URL {}: {}",
+                    url_string, e
+                )))
+            }
+        };
+
+        // key on the parsed URL so equivalent query strings share an entry
+        let cached = match &self.cache {
+            Some(cache) => cache.load(url.as_str()),
+            None => None,
+        };
+        if let (Some(cache), Some(entry)) = (&self.cache, &cached) {
+            if entry.is_fresh(cache.ttl()) {
+                return Self::parse_body(entry.body.as_str());
+            }
+        }
+
+        let mut request = Client::new().get(url.clone());
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag.as_str());
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+            }
+        }
+
+        let mut result = request.send()?;
+
+        if result.status() == StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some(mut entry)) = (&self.cache, cached) {
+                entry.touch();
+                self.store(cache, &entry);
+                return Self::parse_body(entry.body.as_str());
+            }
+        }
+
+        let header_string = |name| {
+            result
+                .headers()
+                .get(name)
+                .and_then(|val| val.to_str().ok())
+                .map(|val| val.to_owned())
+        };
+        let etag = header_string(ETAG);
+        let last_modified = header_string(LAST_MODIFIED);
+        let retry_after = header_string(RETRY_AFTER);
+        let status = result.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(MHWQueryError::RateLimited {
+                retry_after: retry_after.and_then(|secs| secs.trim().parse::<u64>().ok()),
+            });
+        }
+
+        let body = result.text()?;
+        if !status.is_success() {
+            return Err(MHWQueryError::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let found = Self::parse_body(body.as_str())?;
+
+        if let Some(cache) = &self.cache {
+            self.store(
+                cache,
+                &CacheEntry::new(url.as_str(), etag, last_modified, body),
+            );
+        }
+
+        Ok(found)
+    }
+
+    fn clear_cache(&self) -> Result<(), MHWQueryError> {
+        match &self.cache {
+            Some(cache) => match cache.clear() {
+                Ok(()) => Ok(()),
+                Err(e) => Err(MHWQueryError::Internal(format!(
+                    "Unable to clear cache: {}",
+                    e
+                ))),
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn take_cache_errors(&self) -> Vec<MHWQueryError> {
+        match self.cache_errors.lock() {
+            Ok(mut errors) => errors.drain(..).collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+//
+// Snapshot Data Source
+//
+#[derive(Debug)]
+pub struct SnapshotDataSource {
+    database: LocalDatabase,
+}
+
+impl SnapshotDataSource {
+    pub fn load(dir: &Path) -> Result<Self, MHWQueryError> {
+        Ok(Self {
+            database: LocalDatabase::load(dir)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.database.is_empty()
+    }
+}
+
+impl From<LocalDatabase> for SnapshotDataSource {
+    fn from(database: LocalDatabase) -> Self {
+        Self { database }
+    }
+}
+
+impl DataSource for SnapshotDataSource {
+    fn fetch(&self, query: &QueryInfo) -> Result<Value, MHWQueryError> {
+        self.database.query(query)
+    }
+}
+
+//
+// Fixture Data Source
+//
+// Serves collections held in memory so tests can run queries without files or a network
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct FixtureDataSource {
+    database: LocalDatabase,
+}
+
+#[cfg(test)]
+impl FixtureDataSource {
+    pub fn with_collection(mut self, name: &str, entries: Vec<Value>) -> Self {
+        self.database.insert_collection(name, entries);
+        self
+    }
+}
+
+#[cfg(test)]
+impl DataSource for FixtureDataSource {
+    fn fetch(&self, query: &QueryInfo) -> Result<Value, MHWQueryError> {
+        self.database.query(query)
+    }
+}
+
+// Every collection under fixtures/db
+#[cfg(test)]
+pub fn fixture_source() -> FixtureDataSource {
+    let collection = |text: &str| serde_json::from_str::<Vec<Value>>(text).unwrap();
+    FixtureDataSource::default()
+        .with_collection(
+            "armor",
+            collection(include_str!("../../fixtures/db/armor.json")),
+        )
+        .with_collection(
+            "armor/sets",
+            collection(include_str!("../../fixtures/db/armor_sets.json")),
+        )
+        .with_collection(
+            "charms",
+            collection(include_str!("../../fixtures/db/charms.json")),
+        )
+        .with_collection(
+            "decorations",
+            collection(include_str!("../../fixtures/db/decorations.json")),
+        )
+        .with_collection(
+            "weapons",
+            collection(include_str!("../../fixtures/db/weapons.json")),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::armor::{ArmorInfo, ArmorType};
+    use super::super::common::Element;
+    use super::super::query::{QueryFilter, QueryFilterType};
+    use super::super::search::{SearchCategory, SearchResults};
+    use super::super::weapons::WeaponInfo;
+    use super::*;
+
+    #[test]
+    fn armor_decodes_from_fixture() {
+        let query = QueryInfo::find_category(SearchCategory::Armor).with_filter(QueryFilter::new(
+            "type".to_owned(),
+            QueryFilterType::Exact("chest".into()),
+        ));
+        let armor: Vec<ArmorInfo> = query.execute(&fixture_source()).unwrap();
+        assert_eq!(armor.len(), 2);
+
+        let mail = &armor[0];
+        assert_eq!(mail.name, "Rathalos Mail Beta");
+        assert_eq!(mail.type_val, ArmorType::Chest);
+        assert_eq!(mail.defense.max, 80);
+        assert_eq!(mail.slots.len(), 1);
+        assert_eq!(mail.skills[1].skill_name, "Weakness Exploit");
+        assert_eq!(
+            mail.armor_set.as_ref().map(|set| (set.id, set.bonus)),
+            Some((100, Some(1000)))
+        );
+        assert_eq!(mail.crafting.materials[1].item.name, "Rathalos Plate");
+    }
+
+    #[test]
+    fn weapons_decode_from_fixture() {
+        let weapons: Vec<WeaponInfo> = QueryInfo::find_category(SearchCategory::Weapons)
+            .execute(&fixture_source())
+            .unwrap();
+        assert_eq!(weapons.len(), 3);
+
+        let blade = &weapons[2];
+        assert_eq!(blade.name, "Flame Blade I");
+        assert_eq!(blade.attack.raw, 140);
+        assert_eq!(blade.crafting.previous, Some(402));
+        assert!(blade.crafting.craftable);
+        match blade.elements[0].elememt {
+            Element::Fire => assert_eq!(blade.elements[0].damage, 240),
+            ref other => panic!("expected fire, got {}", other),
+        }
+        assert_eq!(blade.durability.len(), 1);
+    }
+
+    #[test]
+    fn name_search_returns_results() {
+        let query = QueryInfo::find_ids("rathalos*beta", SearchCategory::Armor).with_filter(
+            QueryFilter::new("type".to_owned(), QueryFilterType::Exact("legs".into())),
+        );
+        let results: Vec<SearchResults> = query.execute(&fixture_source()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 16);
+        assert_eq!(results[0].name, "Rathalos Greaves Beta");
+    }
+
+    #[test]
+    fn bad_entries_report_their_path() {
+        let source = FixtureDataSource::default().with_collection(
+            "armor",
+            vec![serde_json::json!({ "id": 1, "name": "Broken", "type": "cape" })],
+        );
+        let found: Result<Vec<ArmorInfo>, _> =
+            QueryInfo::find_category(SearchCategory::Armor).execute(&source);
+        match found {
+            Err(MHWQueryError::UnknownEnumValue { path, value }) => {
+                assert_eq!(path, "[0].type");
+                assert_eq!(value, "cape");
+            }
+            other => panic!("expected an unknown value, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use super::query::*;
+use reqwest::{self, StatusCode};
+use serde_json::{self, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_DATABASE_DIR: &str = "mhw_data";
+
+// Every collection the offline store mirrors from mhw-db
+pub const COLLECTIONS: &[&str] = &[
+    "armor",
+    "weapons",
+    "items",
+    "skills",
+    "decorations",
+    "charms",
+    "monsters",
+    "armor/sets",
+];
+
+// Collections like `armor/sets` are nested in the API but flattened on disk
+fn collection_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.json", name.replace('/', "_")))
+}
+
+//
+// Local Database
+//
+#[derive(Debug, Default)]
+pub struct LocalDatabase {
+    collections: HashMap<String, Vec<Value>>,
+}
+
+impl LocalDatabase {
+    // Downloads every collection from the API at `base_url` into `dir`, then loads the result
+    pub fn sync(dir: &Path, base_url: &str) -> Result<Self, MHWQueryError> {
+        if let Err(e) = fs::create_dir_all(dir) {
+            return Err(MHWQueryError::Internal(format!(
+                "Unable to create database directory: {}",
+                e
+            )));
+        }
+
+        for name in COLLECTIONS {
+            let url = format!("{}/{}", base_url, name);
+            let mut result = reqwest::get(url.as_str())?;
+            let status = result.status();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return Err(MHWQueryError::RateLimited { retry_after: None });
+            }
+
+            let text = result.text()?;
+            if !status.is_success() {
+                return Err(MHWQueryError::HttpStatus {
+                    status: status.as_u16(),
+                    body: text,
+                });
+            }
+
+            // make sure it's a usable collection before replacing what's on disk
+            if let Err(e) = serde_json::from_str::<Vec<Value>>(text.as_str()) {
+                return Err(MHWQueryError::from_decode(name.to_string(), e));
+            }
+
+            if let Err(e) = fs::write(collection_path(dir, name), text) {
+                return Err(MHWQueryError::Internal(format!(
+                    "Unable to write {} collection: {}",
+                    name, e
+                )));
+            }
+        }
+
+        Self::load(dir)
+    }
+
+    // Loads whichever collections have been synced into `dir`
+    pub fn load(dir: &Path) -> Result<Self, MHWQueryError> {
+        let mut collections = HashMap::new();
+
+        for name in COLLECTIONS {
+            let path = collection_path(dir, name);
+            if !path.exists() {
+                continue;
+            }
+
+            let text = match fs::read_to_string(&path) {
+                Ok(t) => t,
+                Err(e) => {
+                    return Err(MHWQueryError::Internal(format!(
+                        "Unable to read {} collection: {}",
+                        name, e
+                    )))
+                }
+            };
+            let entries: Vec<Value> = match serde_json::from_str(text.as_str()) {
+                Ok(v) => v,
+                Err(e) => return Err(MHWQueryError::from_decode(name.to_string(), e)),
+            };
+
+            collections.insert((*name).to_owned(), entries);
+        }
+
+        Ok(Self { collections })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn insert_collection(&mut self, name: &str, entries: Vec<Value>) {
+        self.collections.insert(name.to_owned(), entries);
+    }
+
+    // Runs the query against the matching collection, returning a JSON array like the API would
+    pub fn query(&self, query: &QueryInfo) -> Result<Value, MHWQueryError> {
+        let name = query.category().to_string();
+        let entries = match self.collections.get(&name) {
+            Some(entries) => entries,
+            None => {
+                return Err(MHWQueryError::Internal(format!(
+                    "The {} collection has not been synced",
+                    name
+                )))
+            }
+        };
+
+        let found = entries
+            .iter()
+            .filter(|entry| query_matches(query, entry))
+            .map(|entry| match query.projection() {
+                Some(proj) => apply_projection(proj, entry),
+                None => entry.clone(),
+            })
+            .collect();
+
+        Ok(Value::Array(found))
+    }
+}
+
+//
+// Query Engine
+//
+fn query_matches(query: &QueryInfo, entry: &Value) -> bool {
+    query.filters().iter().all(|f| filter_matches(f, entry))
+}
+
+fn filter_matches(filter: &QueryFilter, entry: &Value) -> bool {
+    match filter {
+        QueryFilter::Field { field_name, filter } => {
+            let found = resolve_path(entry, field_name);
+            filter_type_matches(filter, &found)
+        }
+        QueryFilter::And(filters) => filters.iter().all(|f| filter_matches(f, entry)),
+        QueryFilter::Or(filters) => filters.iter().any(|f| filter_matches(f, entry)),
+    }
+}
+
+// Comparisons hold if any value found at the path satisfies them, the same way the API treats
+// arrays such as `slots.rank`; the negative forms hold only if none of them match.
+fn filter_type_matches(filter: &QueryFilterType, found: &[&Value]) -> bool {
+    let any = |test: &dyn Fn(&Value) -> bool| found.iter().any(|v| test(v));
+
+    match filter {
+        QueryFilterType::Exact(val) => any(&|v| compare(v, val) == Some(Ordering::Equal)),
+        QueryFilterType::Like(pattern) => any(&|v| match v.as_str() {
+            Some(s) => like_matches(pattern, s),
+            None => false,
+        }),
+        QueryFilterType::NotEqual(val) => !any(&|v| compare(v, val) == Some(Ordering::Equal)),
+        QueryFilterType::GreaterThan(val) => any(&|v| compare(v, val) == Some(Ordering::Greater)),
+        QueryFilterType::GreaterThanOrEqual(val) => any(&|v| {
+            matches!(
+                compare(v, val),
+                Some(Ordering::Greater) | Some(Ordering::Equal)
+            )
+        }),
+        QueryFilterType::LessThan(val) => any(&|v| compare(v, val) == Some(Ordering::Less)),
+        QueryFilterType::LessThanOrEqual(val) => any(&|v| {
+            matches!(
+                compare(v, val),
+                Some(Ordering::Less) | Some(Ordering::Equal)
+            )
+        }),
+        QueryFilterType::Between(min, max) => any(&|v| {
+            let above = matches!(
+                compare(v, min),
+                Some(Ordering::Greater) | Some(Ordering::Equal)
+            );
+            let below = matches!(
+                compare(v, max),
+                Some(Ordering::Less) | Some(Ordering::Equal)
+            );
+            above && below
+        }),
+        QueryFilterType::In(vals) => any(&|v| {
+            vals.iter()
+                .any(|val| compare(v, val) == Some(Ordering::Equal))
+        }),
+        QueryFilterType::NotIn(vals) => !any(&|v| {
+            vals.iter()
+                .any(|val| compare(v, val) == Some(Ordering::Equal))
+        }),
+        QueryFilterType::Exists(should_exist) => {
+            let exists = found.iter().any(|v| !v.is_null());
+            exists == *should_exist
+        }
+    }
+}
+
+// Collects every value at a dotted path, stepping into arrays along the way
+fn resolve_path<'a>(entry: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut current = vec![entry];
+
+    for part in path.split('.') {
+        let mut next = vec![];
+        for value in current {
+            match value {
+                Value::Object(map) => {
+                    if let Some(child) = map.get(part) {
+                        next.push(child);
+                    }
+                }
+                Value::Array(items) => {
+                    if let Ok(idx) = part.parse::<usize>() {
+                        if let Some(child) = items.get(idx) {
+                            next.push(child);
+                        }
+                    } else {
+                        for item in items {
+                            if let Value::Object(map) = item {
+                                if let Some(child) = map.get(part) {
+                                    next.push(child);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+
+    // a value that is itself an array matches on any of its elements
+    let mut found = vec![];
+    for value in current {
+        found.push(value);
+        if let Value::Array(items) = value {
+            found.extend(items.iter());
+        }
+    }
+    found
+}
+
+fn compare(value: &Value, query_value: &QueryValue) -> Option<Ordering> {
+    match (value, query_value) {
+        (Value::Number(n), QueryValue::Number(q)) => n.as_f64()?.partial_cmp(q),
+        (Value::String(s), QueryValue::Text(q)) => Some(s.as_str().cmp(q.as_str())),
+        (Value::String(s), QueryValue::Number(q)) => s.parse::<f64>().ok()?.partial_cmp(q),
+        (Value::Bool(b), QueryValue::Bool(q)) => Some(b.cmp(q)),
+        _ => None,
+    }
+}
+
+// SQL-style LIKE: `%` matches any run of characters, `_` matches exactly one, case-insensitive
+fn like_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    // matched[j] is true if the pattern so far matches the first j characters of the text
+    let mut matched = vec![false; text.len() + 1];
+    matched[0] = true;
+    for p in pattern {
+        let mut next = vec![false; text.len() + 1];
+        match p {
+            '%' => {
+                let mut seen = false;
+                for j in 0..=text.len() {
+                    seen = seen || matched[j];
+                    next[j] = seen;
+                }
+            }
+            _ => {
+                for j in 1..=text.len() {
+                    next[j] = matched[j - 1] && (p == '_' || p == text[j - 1]);
+                }
+            }
+        }
+        matched = next;
+    }
+
+    matched[text.len()]
+}
+
+fn apply_projection(proj: &QueryProjection, entry: &Value) -> Value {
+    let map = match entry.as_object() {
+        Some(map) => map,
+        None => return entry.clone(),
+    };
+
+    let projected = map
+        .iter()
+        .filter(|(key, _)| proj.fields().iter().any(|field| field == key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<Map<String, Value>>();
+
+    Value::Object(projected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::search::SearchCategory;
+    use super::*;
+    use serde_json::json;
+
+    fn fixture_database() -> LocalDatabase {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/db");
+        LocalDatabase::load(&dir).unwrap()
+    }
+
+    fn field(name: &str, filter: QueryFilterType) -> QueryFilter {
+        QueryFilter::new(name.to_owned(), filter)
+    }
+
+    // Ids of the armor pieces every filter matches
+    fn armor_ids(filters: Vec<QueryFilter>) -> Vec<i64> {
+        let query = filters.into_iter().fold(
+            QueryInfo::find_category(SearchCategory::Armor),
+            |query, filter| query.with_filter(filter),
+        );
+        let found = fixture_database().query(&query).unwrap();
+        found
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn exact_matches() {
+        let name = |name: &str| field("name", QueryFilterType::Exact(name.into()));
+        assert_eq!(armor_ids(vec![name("Kaiser Crown Beta")]), vec![11]);
+        assert_eq!(
+            armor_ids(vec![name("kaiser crown beta")]),
+            Vec::<i64>::new()
+        );
+        assert_eq!(
+            armor_ids(vec![field("rank", QueryFilterType::Exact("low".into()))]),
+            vec![1]
+        );
+        assert_eq!(
+            armor_ids(vec![field("rarity", QueryFilterType::Exact(5.into()))]),
+            vec![13, 17]
+        );
+    }
+
+    #[test]
+    fn like_wildcards_ignore_case() {
+        let like =
+            |pattern: &str| armor_ids(vec![field("name", QueryFilterType::Like(pattern.into()))]);
+        assert_eq!(like("%rathalos%"), vec![10, 12, 14, 15, 16]);
+        assert_eq!(like("BONE%"), vec![13, 17]);
+        assert_eq!(like("Bone _ail Beta"), vec![13]);
+        assert_eq!(like("Bone _ail"), Vec::<i64>::new());
+
+        assert!(like_matches("%", ""));
+        assert!(like_matches("a%c", "ac"));
+        assert!(like_matches("a_c", "ABC"));
+        assert!(!like_matches("a_c", "abbc"));
+        assert!(!like_matches("", "a"));
+    }
+
+    #[test]
+    fn comparisons() {
+        let rarity = |filter| armor_ids(vec![field("rarity", filter)]);
+        assert_eq!(rarity(QueryFilterType::GreaterThan(6.into())), vec![11]);
+        assert_eq!(
+            rarity(QueryFilterType::GreaterThanOrEqual(6.into())),
+            vec![10, 11, 12, 14, 15, 16]
+        );
+        assert_eq!(rarity(QueryFilterType::LessThan(5.into())), vec![1]);
+        assert_eq!(
+            rarity(QueryFilterType::LessThanOrEqual(5.into())),
+            vec![1, 13, 17]
+        );
+        assert_eq!(
+            rarity(QueryFilterType::NotEqual(6.into())),
+            vec![1, 11, 13, 17]
+        );
+        assert_eq!(
+            armor_ids(vec![field(
+                "defense.max",
+                QueryFilterType::Between(72.into(), 80.into())
+            )]),
+            vec![10, 12, 14, 15, 16, 17]
+        );
+    }
+
+    #[test]
+    fn set_and_existence() {
+        assert_eq!(
+            armor_ids(vec![field(
+                "type",
+                QueryFilterType::In(vec!["gloves".into(), "waist".into()])
+            )]),
+            vec![14, 15]
+        );
+        // pieces with no slots at all have nothing to match, so they count as not in
+        assert_eq!(
+            armor_ids(vec![field(
+                "slots.rank",
+                QueryFilterType::NotIn(vec![1.into()])
+            )]),
+            vec![1, 11, 12, 14, 16]
+        );
+        assert_eq!(
+            armor_ids(vec![field("armorSet.bonus", QueryFilterType::Exists(true))]),
+            vec![10, 12, 14, 15, 16]
+        );
+        assert_eq!(
+            armor_ids(vec![field(
+                "armorSet.bonus",
+                QueryFilterType::Exists(false)
+            )]),
+            vec![1, 11, 13, 17]
+        );
+    }
+
+    #[test]
+    fn paths_step_through_arrays() {
+        assert_eq!(
+            armor_ids(vec![field(
+                "skills.skill",
+                QueryFilterType::Exact(3.into())
+            )]),
+            vec![12, 16]
+        );
+        assert_eq!(
+            armor_ids(vec![field("slots.rank", QueryFilterType::Exact(3.into()))]),
+            vec![11]
+        );
+        // numeric parts pick a single element
+        assert_eq!(
+            armor_ids(vec![field("slots.1.rank", QueryFilterType::Exists(true))]),
+            vec![15, 17]
+        );
+        assert_eq!(
+            armor_ids(vec![
+                field("skills.skillName", QueryFilterType::Like("attack%".into())),
+                field("type", QueryFilterType::Exact("chest".into())),
+            ]),
+            vec![12, 13]
+        );
+    }
+
+    #[test]
+    fn projection_keeps_listed_fields() {
+        let query = QueryInfo::find_ids("crown", SearchCategory::Armor);
+        assert_eq!(
+            fixture_database().query(&query).unwrap(),
+            json!([{ "id": 11, "name": "Kaiser Crown Beta", "type": "head" }])
+        );
+    }
+
+    #[test]
+    fn unsynced_collection_is_an_error() {
+        let query = QueryInfo::find_category(SearchCategory::Monsters);
+        assert!(fixture_database().query(&query).is_err());
+    }
+}
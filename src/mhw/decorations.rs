@@ -0,0 +1,227 @@
+use super::catalog::CatalogCollection;
+use super::common::{
+    fonts::*, rarity::*, GuiDetails, MhwEvent, MhwWindowContents, SkillRank, Slot,
+};
+use super::entry_display::EntryRequest;
+use super::search::SearchCategory;
+use crate::widgets::table_view::*;
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const SLOT_POPUP_WIDTH: f32 = 360f32;
+const SLOT_POPUP_HEIGHT: f32 = 300f32;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecorationInfo {
+    pub id: i32,
+    pub name: String,
+    pub rarity: u32,
+    pub slot: i32,
+    pub skills: Vec<SkillRank>,
+
+    // internal details
+    #[serde(skip)]
+    skills_cache: SimpleTableDataModel,
+}
+
+impl DecorationInfo {
+    // A decoration fits any slot at least as large as its own
+    pub fn fits(&self, slot: &Slot) -> bool {
+        self.slot <= slot.rank
+    }
+
+    fn skills_summary(&self) -> String {
+        self.skills
+            .iter()
+            .map(|skill| format!("{} +{}", skill.skill_name, skill.level))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn skills_data(&mut self) -> &TableDataModel {
+        if self.skills_cache.is_empty() {
+            self.skills_cache.set_columns(2);
+            let skills = &self.skills;
+            for skill in skills {
+                let link = EntryRequest::new(SearchCategory::Skills, skill.skill);
+                self.skills_cache.push_link(skill.skill_name.clone(), link);
+                self.skills_cache.push(skill.level.to_string());
+            }
+        }
+        &self.skills_cache
+    }
+}
+
+impl MhwWindowContents for DecorationInfo {
+    fn build_window<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        _details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        //=======================================
+        // Name/ID
+        ui.with_font(FONT_IDX_HEADER, || {
+            let imstring = ImString::new(self.name.as_str());
+            ui.text_colored(rarity_color(self.rarity), &imstring);
+        });
+        ui.with_font(FONT_IDX_MINI, || {
+            let id_string = format!("id: [{}]", self.id);
+            ui.text(id_string.as_str());
+            ui.same_line(0.0);
+            ui.text(format!("Slot Size {}", self.slot));
+        });
+
+        //=======================================
+        // Lists section
+        ui.separator();
+        draw_table(ui, "Skills", self.skills_data(), event_queue);
+    }
+}
+
+pub fn slot_label(slot: Option<&Slot>) -> &'static str {
+    match slot {
+        Some(val) => match val.rank {
+            1 => "[1]",
+            2 => "[2]",
+            3 => "[3]",
+            _ => "[E]",
+        },
+        None => "[-]",
+    }
+}
+
+//
+// Slot Pick
+//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotPick {
+    Decoration(i32),
+    Clear,
+    Retry, // the decorations failed to load and should be asked for again
+}
+
+// Draws a button for one slot. Clicking it lists every decoration that fits in the slot and
+// returns whichever one gets picked. Call it inside `ui.with_id` when drawing several slots.
+pub fn slot_button<'a>(
+    ui: &Ui<'a>,
+    label: &ImStr,
+    slot: &Slot,
+    decorations: &CatalogCollection<DecorationInfo>,
+    allow_clear: bool,
+) -> Option<SlotPick> {
+    let mut picked = None;
+
+    if ui.small_button(label) {
+        ui.open_popup(im_str!("slot_decorations"));
+    }
+    if ui.is_item_hovered() {
+        match decorations.get() {
+            Some(decorations) => {
+                let count = decorations.iter().filter(|d| d.fits(slot)).count();
+                ui.tooltip_text(format!("{} decorations fit this slot", count));
+            }
+            None if decorations.is_pending() => ui.tooltip_text("Loading decorations..."),
+            None => ui.tooltip_text("Decorations failed to load"),
+        }
+    }
+
+    ui.popup(im_str!("slot_decorations"), || {
+        let decorations = match decorations.get() {
+            Some(decorations) => decorations,
+            None if decorations.is_pending() => {
+                ui.text("Loading decorations...");
+                return;
+            }
+            None => {
+                ui.text("Decorations failed to load");
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Retry")) {
+                    picked = Some(SlotPick::Retry);
+                    ui.close_current_popup();
+                }
+                return;
+            }
+        };
+
+        let mut fitting = decorations
+            .iter()
+            .filter(|d| d.fits(slot))
+            .collect::<Vec<_>>();
+        fitting.sort_by(|a, b| b.slot.cmp(&a.slot).then(a.name.cmp(&b.name)));
+
+        ui.child_frame(
+            im_str!("slot_decoration_list"),
+            (SLOT_POPUP_WIDTH, SLOT_POPUP_HEIGHT),
+        )
+        .show_borders(false)
+        .build(|| {
+            if allow_clear
+                && ui.selectable(
+                    im_str!("<Empty>"),
+                    false,
+                    ImGuiSelectableFlags::empty(),
+                    (0.0, 0.0),
+                )
+            {
+                picked = Some(SlotPick::Clear);
+                ui.close_current_popup();
+            }
+            for deco in fitting {
+                let label = ImString::from(format!(
+                    "[{}] {}  ({})##{}",
+                    deco.slot,
+                    deco.name,
+                    deco.skills_summary(),
+                    deco.id
+                ));
+                if ui.selectable(&label, false, ImGuiSelectableFlags::empty(), (0.0, 0.0)) {
+                    picked = Some(SlotPick::Decoration(deco.id));
+                    ui.close_current_popup();
+                }
+            }
+        });
+    });
+
+    picked
+}
+
+// Draws the three slot markers for a piece of equipment. Clicking a slot lists every decoration
+// that fits in it, and picking one from that list opens it.
+pub fn draw_slots<'a>(
+    ui: &Ui<'a>,
+    slots: &[Slot],
+    details: &mut GuiDetails,
+    event_queue: &mut VecDeque<MhwEvent>,
+) {
+    let decorations = &mut details.catalog.decorations;
+    decorations.get_or_request(event_queue, MhwEvent::LoadDecorations);
+
+    let mut picked = None;
+    for idx in 0..3 {
+        let slot = slots.get(idx);
+        let label = slot_label(slot);
+        match slot {
+            Some(slot) => ui.with_id(idx as i32, || {
+                let label = ImString::new(label);
+                if let Some(pick) = slot_button(ui, &label, slot, decorations, false) {
+                    picked = Some(pick);
+                }
+            }),
+            None => ui.text(label),
+        }
+        ui.same_line(0.0);
+    }
+    ui.new_line();
+
+    match picked {
+        Some(SlotPick::Decoration(id)) => {
+            let request = EntryRequest::new(SearchCategory::Decorations, id);
+            event_queue.push_back(MhwEvent::RequestEntry(request));
+        }
+        Some(SlotPick::Retry) => decorations.retry(),
+        _ => {}
+    }
+}
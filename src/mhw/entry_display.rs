@@ -1,21 +1,101 @@
 use super::common::{fonts::*, MhwEvent, MhwGui};
-use super::{armor::*, items::*, weapons::*};
+use super::data_source::DataSource;
+use super::query::*;
+use super::search::SearchCategory;
+use super::{
+    armor::*, armor_sets::*, charms::*, decorations::*, items::*, monsters::*, skills::*,
+    weapons::*,
+};
 use crate::mhw::common::GuiDetails;
 use crate::mhw::common::MhwWindowContents;
 use imgui::*;
+use serde::de::DeserializeOwned;
 use std::collections::VecDeque;
 
-#[derive(Debug)]
+//
+// Entry Request
+//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntryRequest {
+    pub category: SearchCategory,
+    pub id: i32,
+}
+
+impl EntryRequest {
+    pub fn new(category: SearchCategory, id: i32) -> Self {
+        Self { category, id }
+    }
+}
+
+fn fetch_first<T>(source: &dyn DataSource, query: &QueryInfo) -> Result<Option<T>, MHWQueryError>
+where
+    T: DeserializeOwned,
+{
+    let mut found: Vec<T> = query.execute(source)?;
+    if found.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(found.remove(0)))
+    }
+}
+
+// Loads the full entry for a request; this blocks, so it belongs on the query worker
+pub fn fetch_entry(
+    source: &dyn DataSource,
+    request: &EntryRequest,
+) -> Result<EntryDisplayState, MHWQueryError> {
+    let filter = QueryFilter::new("id".to_owned(), QueryFilterType::Exact(request.id.into()));
+    let query = QueryInfo::find_category(request.category).with_filter(filter);
+
+    let state = match request.category {
+        SearchCategory::Armor => fetch_first(source, &query)?.map(EntryDisplayState::Armor),
+        SearchCategory::Weapons => fetch_first(source, &query)?.map(EntryDisplayState::Weapon),
+        SearchCategory::Items => fetch_first(source, &query)?.map(EntryDisplayState::Item),
+        SearchCategory::Skills => fetch_first(source, &query)?.map(EntryDisplayState::Skill),
+        SearchCategory::Decorations => {
+            fetch_first(source, &query)?.map(EntryDisplayState::Decoration)
+        }
+        SearchCategory::Charms => fetch_first(source, &query)?.map(EntryDisplayState::Charm),
+        SearchCategory::Monsters => fetch_first(source, &query)?.map(EntryDisplayState::Monster),
+        SearchCategory::ArmorSets => fetch_first(source, &query)?.map(EntryDisplayState::ArmorSet),
+        _ => None,
+    };
+
+    Ok(state.unwrap_or(EntryDisplayState::None))
+}
+
+#[derive(Debug, Default)]
 pub enum EntryDisplayState {
+    #[default]
     None,
+    Loading(EntryRequest),
+    Failed(EntryRequest, String),
     Armor(ArmorInfo),
     Weapon(WeaponInfo),
     Item(ItemInfo),
+    Skill(SkillInfo),
+    Decoration(DecorationInfo),
+    Charm(CharmInfo),
+    Monster(MonsterInfo),
+    ArmorSet(ArmorSetInfo),
 }
 
-impl Default for EntryDisplayState {
-    fn default() -> Self {
-        EntryDisplayState::None
+impl EntryDisplayState {
+    pub fn name(&self) -> Option<&str> {
+        let name = match self {
+            EntryDisplayState::None
+            | EntryDisplayState::Loading(_)
+            | EntryDisplayState::Failed(_, _) => return None,
+            EntryDisplayState::Armor(armor) => &armor.name,
+            EntryDisplayState::Weapon(weapon) => &weapon.name,
+            EntryDisplayState::Item(item) => &item.name,
+            EntryDisplayState::Skill(skill) => &skill.name,
+            EntryDisplayState::Decoration(deco) => &deco.name,
+            EntryDisplayState::Charm(charm) => &charm.name,
+            EntryDisplayState::Monster(monster) => &monster.name,
+            EntryDisplayState::ArmorSet(set) => &set.name,
+        };
+        Some(name.as_str())
     }
 }
 
@@ -35,9 +115,16 @@ impl MhwGui for EntryDisplayState {
 
         let title = match self {
             EntryDisplayState::None => im_str!("<Nothing Selected>"),
+            EntryDisplayState::Loading(_) => im_str!("Loading..."),
+            EntryDisplayState::Failed(_, _) => im_str!("Failed"),
             EntryDisplayState::Armor(_) => im_str!("Armor Info"),
             EntryDisplayState::Weapon(_) => im_str!("Weapon Info"),
             EntryDisplayState::Item(_) => im_str!("Item Info"),
+            EntryDisplayState::Skill(_) => im_str!("Skill Info"),
+            EntryDisplayState::Decoration(_) => im_str!("Decoration Info"),
+            EntryDisplayState::Charm(_) => im_str!("Charm Info"),
+            EntryDisplayState::Monster(_) => im_str!("Monster Info"),
+            EntryDisplayState::ArmorSet(_) => im_str!("Armor Set Info"),
         };
 
         let window = ui
@@ -46,10 +133,22 @@ impl MhwGui for EntryDisplayState {
             .size(remaining_size, ImGuiCond::Always)
             .flags(ImGuiWindowFlags::NoCollapse | ImGuiWindowFlags::NoResize);
 
-        // TODO: Keep UI responsive with async getting data and placeholder UI until loaded
         ui.with_font(FONT_IDX_WINDOW_TITLE, || {
             match self {
                 EntryDisplayState::None => window.build(|| {}),
+                EntryDisplayState::Loading(request) => window.build(|| {
+                    ui.text(format!("Loading {} [{}]...", request.category, request.id));
+                }),
+                EntryDisplayState::Failed(request, message) => window.build(|| {
+                    ui.text("Failed:");
+                    ui.same_line(0.0);
+                    ui.with_font(FONT_IDX_NORMAL, || {
+                        ui.text_wrapped(&ImString::new(message.as_str()));
+                    });
+                    if ui.button(im_str!("Retry"), (0.0, 0.0)) {
+                        event_queue.push_back(MhwEvent::RequestEntry(*request));
+                    }
+                }),
                 EntryDisplayState::Armor(ref mut armor) => {
                     window.build(|| armor.build_window(ui, details, event_queue))
                 }
@@ -59,6 +158,21 @@ impl MhwGui for EntryDisplayState {
                 EntryDisplayState::Item(ref mut item) => {
                     window.build(|| item.build_window(ui, details, event_queue))
                 }
+                EntryDisplayState::Skill(ref mut skill) => {
+                    window.build(|| skill.build_window(ui, details, event_queue))
+                }
+                EntryDisplayState::Decoration(ref mut decoration) => {
+                    window.build(|| decoration.build_window(ui, details, event_queue))
+                }
+                EntryDisplayState::Charm(ref mut charm) => {
+                    window.build(|| charm.build_window(ui, details, event_queue))
+                }
+                EntryDisplayState::Monster(ref mut monster) => {
+                    window.build(|| monster.build_window(ui, details, event_queue))
+                }
+                EntryDisplayState::ArmorSet(ref mut set) => {
+                    window.build(|| set.build_window(ui, details, event_queue))
+                }
             };
         });
     }
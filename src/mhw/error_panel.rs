@@ -0,0 +1,95 @@
+use super::common::{GuiDetails, MhwEvent, MhwGui};
+use super::query::MHWQueryError;
+use imgui::*;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const ERROR_PANEL_WIDTH: f32 = 360f32;
+const ERROR_PANEL_MARGIN: f32 = 8f32;
+const MAX_ERRORS: usize = 5;
+const ERROR_LIFETIME_SECS: u64 = 15;
+
+const_rgb_int!(ERROR_COLOR, 235, 87, 87);
+
+//
+// Error Toast
+//
+#[derive(Debug)]
+pub struct ErrorToast {
+    context: String, // what the app was trying to do when it failed
+    error: MHWQueryError,
+    shown_at: Instant,
+}
+
+//
+// Error Panel
+//
+#[derive(Debug, Default)]
+pub struct ErrorPanel {
+    toasts: VecDeque<ErrorToast>,
+}
+
+impl ErrorPanel {
+    pub fn push(&mut self, context: &str, error: MHWQueryError) {
+        // keep only the newest few so a flaky connection can't bury the screen
+        if self.toasts.len() >= MAX_ERRORS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(ErrorToast {
+            context: context.to_owned(),
+            error,
+            shown_at: Instant::now(),
+        });
+    }
+}
+
+impl MhwGui for ErrorPanel {
+    fn layout<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        _event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        let lifetime = Duration::from_secs(ERROR_LIFETIME_SECS);
+        self.toasts
+            .retain(|toast| toast.shown_at.elapsed() < lifetime);
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let logical_size = ui.frame_size().logical_size;
+        let window_pos = (
+            logical_size.0 as f32 - ERROR_PANEL_WIDTH - ERROR_PANEL_MARGIN,
+            details.next_start_pos.1 + ERROR_PANEL_MARGIN,
+        );
+
+        // a height of zero lets the window fit however many errors are showing
+        let window = ui
+            .window(im_str!("Errors"))
+            .position(window_pos, ImGuiCond::Always)
+            .size((ERROR_PANEL_WIDTH, 0.0), ImGuiCond::Always)
+            .flags(ImGuiWindowFlags::NoDecoration);
+        window.build(|| {
+            let mut dismissed = None;
+            for (idx, toast) in self.toasts.iter().enumerate() {
+                ui.with_id(idx as i32, || {
+                    if idx > 0 {
+                        ui.separator();
+                    }
+                    ui.text_colored(
+                        ERROR_COLOR,
+                        &ImString::new(format!("{}: {}", toast.error.kind(), toast.context)),
+                    );
+                    ui.same_line(ERROR_PANEL_WIDTH - 70.0);
+                    if ui.small_button(im_str!("Dismiss")) {
+                        dismissed = Some(idx);
+                    }
+                    ui.text_wrapped(&ImString::new(toast.error.to_string()));
+                });
+            }
+            if let Some(idx) = dismissed {
+                self.toasts.remove(idx);
+            }
+        });
+    }
+}
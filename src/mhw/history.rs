@@ -0,0 +1,245 @@
+use super::common::{fonts::*, GuiDetails, MhwEvent, MhwGui};
+use super::entry_display::EntryRequest;
+use imgui::*;
+use std::collections::VecDeque;
+
+const MAX_HISTORY: usize = 50;
+const BREADCRUMB_COUNT: usize = 6;
+const BREADCRUMB_HEIGHT: f32 = 34.0;
+
+#[derive(Debug)]
+struct HistoryEntry {
+    request: EntryRequest,
+    name: Option<String>, // filled in once the entry has loaded
+}
+
+impl HistoryEntry {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{} [{}]", self.request.category, self.request.id),
+        }
+    }
+}
+
+//
+// Navigation History
+//
+// Every entry shown in the entry pane, oldest first, so it can be stepped back and forward through
+#[derive(Debug, Default)]
+pub struct NavigationHistory {
+    entries: VecDeque<HistoryEntry>,
+    current: usize, // index into entries, only meaningful when there are any
+}
+
+impl NavigationHistory {
+    fn current_request(&self) -> Option<EntryRequest> {
+        self.entries.get(self.current).map(|entry| entry.request)
+    }
+
+    // Opening something new drops whatever we could have gone forward to
+    pub fn visit(&mut self, request: EntryRequest) {
+        if self.current_request() == Some(request) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.current + 1);
+        }
+        self.entries.push_back(HistoryEntry {
+            request,
+            name: None,
+        });
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.pop_front();
+        }
+        self.current = self.entries.len() - 1;
+    }
+
+    pub fn set_name(&mut self, request: EntryRequest, name: &str) {
+        for entry in self.entries.iter_mut() {
+            if entry.request == request {
+                entry.name = Some(name.to_owned());
+            }
+        }
+    }
+
+    // These return the entry to show, or None when there's nowhere to go
+    pub fn back(&mut self) -> Option<EntryRequest> {
+        self.go_to(self.current.checked_sub(1)?)
+    }
+
+    pub fn forward(&mut self) -> Option<EntryRequest> {
+        self.go_to(self.current + 1)
+    }
+
+    pub fn go_to(&mut self, index: usize) -> Option<EntryRequest> {
+        if index == self.current || index >= self.entries.len() {
+            return None;
+        }
+        self.current = index;
+        self.current_request()
+    }
+}
+
+// Mouse back/forward buttons, or Alt+Left/Right
+fn shortcuts(ui: &Ui, event_queue: &mut VecDeque<MhwEvent>) {
+    let imgui = ui.imgui();
+    let alt_pressed =
+        |key: ImGuiKey| imgui.key_alt() && imgui.is_key_pressed(imgui.get_key_index(key));
+    if imgui.is_mouse_clicked(ImMouseButton::Extra1) || alt_pressed(ImGuiKey::LeftArrow) {
+        event_queue.push_back(MhwEvent::NavigateBack);
+    }
+    if imgui.is_mouse_clicked(ImMouseButton::Extra2) || alt_pressed(ImGuiKey::RightArrow) {
+        event_queue.push_back(MhwEvent::NavigateForward);
+    }
+}
+
+impl MhwGui for NavigationHistory {
+    fn layout<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        shortcuts(ui, event_queue);
+
+        // sits above the entry pane and pushes it down
+        let logical_size = ui.frame_size().logical_size;
+        let draw_cursor_pos = details.next_start_pos;
+        details.next_start_pos.1 += BREADCRUMB_HEIGHT;
+
+        let window = ui
+            .window(im_str!("History"))
+            .position(draw_cursor_pos, ImGuiCond::Always)
+            .size(
+                (logical_size.0 as f32 - draw_cursor_pos.0, BREADCRUMB_HEIGHT),
+                ImGuiCond::Always,
+            )
+            .flags(ImGuiWindowFlags::NoDecoration);
+
+        window.build(|| {
+            if ui.small_button(im_str!("<")) {
+                event_queue.push_back(MhwEvent::NavigateBack);
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!(">")) {
+                event_queue.push_back(MhwEvent::NavigateForward);
+            }
+
+            // the last few entries, moved back if needed so the current one is always there
+            let start = self
+                .entries
+                .len()
+                .saturating_sub(BREADCRUMB_COUNT)
+                .min(self.current);
+            let shown = self.entries.iter().enumerate().skip(start);
+            for (idx, entry) in shown.take(BREADCRUMB_COUNT) {
+                ui.same_line(0.0);
+                if idx > start {
+                    ui.text("/");
+                    ui.same_line(0.0);
+                }
+                ui.with_id(idx as i32, || {
+                    let label = ImString::from(entry.label());
+                    if idx == self.current {
+                        ui.with_font(FONT_IDX_WINDOW_TITLE, || ui.text(&label));
+                    } else if ui.small_button(&label) {
+                        event_queue.push_back(MhwEvent::NavigateTo(idx));
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::search::SearchCategory;
+    use super::*;
+
+    fn item(id: i32) -> EntryRequest {
+        EntryRequest::new(SearchCategory::Items, id)
+    }
+
+    fn ids(history: &NavigationHistory) -> Vec<i32> {
+        history
+            .entries
+            .iter()
+            .map(|entry| entry.request.id)
+            .collect()
+    }
+
+    #[test]
+    fn visits_skip_the_current_entry() {
+        let mut history = NavigationHistory::default();
+        assert!(history.back().is_none());
+        assert!(history.forward().is_none());
+
+        history.visit(item(1));
+        history.visit(item(2));
+        history.visit(item(2));
+        assert_eq!(ids(&history), vec![1, 2]);
+        assert_eq!(history.current_request(), Some(item(2)));
+    }
+
+    #[test]
+    fn back_forward_and_go_to() {
+        let mut history = NavigationHistory::default();
+        for id in 1..=4 {
+            history.visit(item(id));
+        }
+        assert_eq!(history.back(), Some(item(3)));
+        assert_eq!(history.back(), Some(item(2)));
+        assert_eq!(history.forward(), Some(item(3)));
+        assert_eq!(history.go_to(0), Some(item(1)));
+        assert!(history.back().is_none());
+        assert!(history.go_to(0).is_none());
+        assert!(history.go_to(4).is_none());
+        assert_eq!(history.go_to(3), Some(item(4)));
+        assert!(history.forward().is_none());
+    }
+
+    #[test]
+    fn visiting_drops_the_forward_entries() {
+        let mut history = NavigationHistory::default();
+        for id in 1..=4 {
+            history.visit(item(id));
+        }
+        history.go_to(1);
+        history.visit(item(9));
+        assert_eq!(ids(&history), vec![1, 2, 9]);
+        assert!(history.forward().is_none());
+        assert_eq!(history.back(), Some(item(2)));
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let mut history = NavigationHistory::default();
+        for id in 0..(MAX_HISTORY as i32 + 5) {
+            history.visit(item(id));
+        }
+        assert_eq!(history.entries.len(), MAX_HISTORY);
+        assert_eq!(ids(&history)[0], 5);
+        assert_eq!(history.current, MAX_HISTORY - 1);
+        assert_eq!(
+            history.current_request(),
+            Some(item(MAX_HISTORY as i32 + 4))
+        );
+        assert_eq!(history.back(), Some(item(MAX_HISTORY as i32 + 3)));
+    }
+
+    #[test]
+    fn names_label_every_matching_entry() {
+        let mut history = NavigationHistory::default();
+        history.visit(item(1));
+        history.visit(item(2));
+        history.visit(item(1));
+        history.set_name(item(1), "Iron Ore");
+        let labels = history
+            .entries
+            .iter()
+            .map(HistoryEntry::label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Iron Ore", "items [2]", "Iron Ore"]);
+    }
+}
@@ -0,0 +1,168 @@
+use super::armor::ArmorInfo;
+use super::charms::CharmInfo;
+use super::common::CraftingCost;
+use super::data_source::DataSource;
+use super::monsters::MonsterInfo;
+use super::query::{MHWQueryError, QueryInfo};
+use super::search::SearchCategory;
+use super::weapons::WeaponInfo;
+
+//
+// Item Source
+//
+// How one monster, armor piece, weapon or charm relates to an item
+#[derive(Debug)]
+pub enum ItemSourceKind {
+    Drop {
+        rank: String,
+        method: String, // carve, capture, etc. with the part when there is one
+        quantity: i32,
+        chance: i32, // percent
+    },
+    Habitat {
+        location: String, // somewhere a monster that drops the item can be hunted
+    },
+    Crafted {
+        quantity: i32,
+    },
+    Upgraded {
+        quantity: i32,
+    },
+}
+
+#[derive(Debug)]
+pub struct ItemSource {
+    pub item: i32,
+    pub category: SearchCategory, // what `id` and `name` refer to
+    pub id: i32,
+    pub name: String,
+    pub kind: ItemSourceKind,
+}
+
+impl ItemSource {
+    fn material(
+        category: SearchCategory,
+        id: i32,
+        name: &str,
+        cost: &CraftingCost,
+        upgrade: bool,
+    ) -> Self {
+        let quantity = cost.quantity;
+        Self {
+            item: cost.item.id,
+            category,
+            id,
+            name: name.to_owned(),
+            kind: if upgrade {
+                ItemSourceKind::Upgraded { quantity }
+            } else {
+                ItemSourceKind::Crafted { quantity }
+            },
+        }
+    }
+}
+
+fn monster_sources(monster: &MonsterInfo, sources: &mut Vec<ItemSource>) {
+    let source = |item: i32, kind: ItemSourceKind| ItemSource {
+        item,
+        category: SearchCategory::Monsters,
+        id: monster.id,
+        name: monster.name.clone(),
+        kind,
+    };
+
+    for reward in &monster.rewards {
+        let item = reward.item.id;
+        for condition in &reward.conditions {
+            let method = match &condition.subtype {
+                Some(subtype) => format!("{} ({})", condition.type_val, subtype),
+                None => condition.type_val.clone(),
+            };
+            sources.push(source(
+                item,
+                ItemSourceKind::Drop {
+                    rank: condition.rank.clone(),
+                    method,
+                    quantity: condition.quantity,
+                    chance: condition.chance,
+                },
+            ));
+        }
+        for location in &monster.locations {
+            sources.push(source(
+                item,
+                ItemSourceKind::Habitat {
+                    location: location.name.clone(),
+                },
+            ));
+        }
+    }
+}
+
+// Everything that drops or consumes an item, keyed by `item` so views can filter for theirs.
+// mhw-db has no gathering points, so drops are the only way items are known to be found.
+// Decorations aren't crafted from set recipes (they come from melding), so they never show up.
+pub fn load_item_sources(source: &dyn DataSource) -> Result<Vec<ItemSource>, MHWQueryError> {
+    let monsters: Vec<MonsterInfo> =
+        QueryInfo::find_category(SearchCategory::Monsters).execute(source)?;
+    let armor: Vec<ArmorInfo> = QueryInfo::find_category(SearchCategory::Armor).execute(source)?;
+    let weapons: Vec<WeaponInfo> =
+        QueryInfo::find_category(SearchCategory::Weapons).execute(source)?;
+    let charms: Vec<CharmInfo> =
+        QueryInfo::find_category(SearchCategory::Charms).execute(source)?;
+
+    let mut sources = vec![];
+    for monster in &monsters {
+        monster_sources(monster, &mut sources);
+    }
+    for piece in &armor {
+        let name = piece.name.as_str();
+        for cost in &piece.crafting.materials {
+            sources.push(ItemSource::material(
+                SearchCategory::Armor,
+                piece.id,
+                name,
+                cost,
+                false,
+            ));
+        }
+    }
+    for weapon in &weapons {
+        let crafting = &weapon.crafting;
+        let name = weapon.name.as_str();
+        for cost in &crafting.crafting_materials {
+            sources.push(ItemSource::material(
+                SearchCategory::Weapons,
+                weapon.id,
+                name,
+                cost,
+                false,
+            ));
+        }
+        for cost in &crafting.upgrade_materials {
+            sources.push(ItemSource::material(
+                SearchCategory::Weapons,
+                weapon.id,
+                name,
+                cost,
+                true,
+            ));
+        }
+    }
+    for charm in &charms {
+        // every rank is its own recipe; the first is crafted and the rest upgrade from it
+        for rank in &charm.ranks {
+            let upgrade = !rank.crafting.craftable;
+            for cost in &rank.crafting.materials {
+                sources.push(ItemSource::material(
+                    SearchCategory::Charms,
+                    charm.id,
+                    rank.name.as_str(),
+                    cost,
+                    upgrade,
+                ));
+            }
+        }
+    }
+    Ok(sources)
+}
@@ -1,9 +1,14 @@
 use crate::mhw::common::{fonts::*, rarity::rarity_color, GuiDetails, MhwEvent, MhwWindowContents};
+use crate::mhw::entry_display::EntryRequest;
+use crate::mhw::item_sources::{ItemSource, ItemSourceKind};
+use crate::mhw::monsters::title_case;
+use crate::mhw::search::SearchCategory;
+use crate::widgets::table_view::*;
 use imgui::*;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase", default)]
 pub struct ItemInfo {
     pub id: i32,
@@ -12,14 +17,76 @@ pub struct ItemInfo {
     pub rarity: u32,
     pub carry_limit: u32,
     pub value: u32,
+
+    // internal details
+    #[serde(skip)]
+    drops_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    habitats_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    used_in_cache: SimpleTableDataModel,
+}
+
+impl ItemInfo {
+    fn set_sources(&mut self, sources: &[&ItemSource]) {
+        self.drops_cache.set_columns(5);
+        self.habitats_cache.set_columns(2);
+        self.used_in_cache.set_columns(4);
+
+        let mut habitats = BTreeMap::<&str, Vec<&str>>::new();
+        for source in sources {
+            match &source.kind {
+                ItemSourceKind::Drop {
+                    rank,
+                    method,
+                    quantity,
+                    chance,
+                } => {
+                    let link = EntryRequest::new(source.category, source.id);
+                    self.drops_cache.push_link(source.name.clone(), link);
+                    self.drops_cache.push(title_case(rank.as_str()));
+                    self.drops_cache.push(title_case(method.as_str()));
+                    self.drops_cache.push(format!("x{}", quantity));
+                    self.drops_cache.push(format!("{}%", chance));
+                }
+                ItemSourceKind::Habitat { location } => {
+                    let monsters = habitats.entry(location.as_str()).or_default();
+                    if !monsters.contains(&source.name.as_str()) {
+                        monsters.push(source.name.as_str());
+                    }
+                }
+                ItemSourceKind::Crafted { quantity } | ItemSourceKind::Upgraded { quantity } => {
+                    let category = match source.category {
+                        SearchCategory::Armor => "Armor",
+                        SearchCategory::Weapons => "Weapon",
+                        SearchCategory::Charms => "Charm",
+                        _ => "",
+                    };
+                    let step = match source.kind {
+                        ItemSourceKind::Upgraded { .. } => "Upgrade",
+                        _ => "Craft",
+                    };
+                    let link = EntryRequest::new(source.category, source.id);
+                    self.used_in_cache.push_link(source.name.clone(), link);
+                    self.used_in_cache.push(category.to_owned());
+                    self.used_in_cache.push(step.to_owned());
+                    self.used_in_cache.push(format!("x{}", quantity));
+                }
+            }
+        }
+        for (location, monsters) in habitats {
+            self.habitats_cache.push(location.to_owned());
+            self.habitats_cache.push(monsters.join(", "));
+        }
+    }
 }
 
 impl MhwWindowContents for ItemInfo {
     fn build_window<'a>(
         &mut self,
         ui: &Ui<'a>,
-        _details: &mut GuiDetails,
-        _event_queue: &mut VecDeque<MhwEvent>, // will need this when linking between objects
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
     ) {
         //=======================================
         // Name/ID
@@ -62,5 +129,39 @@ impl MhwWindowContents for ItemInfo {
         ui.with_font(FONT_IDX_NORMAL, || {
             ui.text(self.carry_limit.to_string());
         });
+
+        //=======================================
+        // Sources section
+        ui.columns(1, im_str!("item_sources"), false);
+        ui.separator();
+        if self.drops_cache.is_empty() {
+            match details.catalog.item_sources(self.id, event_queue) {
+                Some(sources) => self.set_sources(&sources),
+                None => {
+                    let catalog = &mut details.catalog;
+                    ui.with_font(FONT_IDX_NORMAL, || {
+                        if catalog.item_sources.is_pending() {
+                            ui.text("Loading drops and recipes...");
+                        } else {
+                            ui.text("Drops and recipes failed to load");
+                            ui.same_line(0.0);
+                            if ui.small_button(im_str!("Retry")) {
+                                catalog.item_sources.retry();
+                            }
+                        }
+                    });
+                    return;
+                }
+            }
+        }
+
+        draw_table(ui, "Dropped By", &self.drops_cache, event_queue);
+        draw_table(ui, "Hunting Grounds", &self.habitats_cache, event_queue);
+        ui.with_font(FONT_IDX_MINI, || {
+            ui.text_wrapped(im_str!(
+                "Gathering points aren't in the data; these are where monsters that drop it live."
+            ));
+        });
+        draw_table(ui, "Used In", &self.used_in_cache, event_queue);
     }
 }
@@ -0,0 +1,556 @@
+use super::armor::{ArmorInfo, ArmorType};
+use super::catalog::Catalog;
+use super::charms::CharmInfo;
+use super::common::{fonts::*, GuiDetails, MhwEvent, MhwGui, SkillRank, SkillRankModifiers, Slot};
+use super::damage::{weapon_damage, DamageSkills, EffectiveDamage};
+use super::decorations::*;
+use super::entry_display::{EntryDisplayState, EntryRequest};
+use super::search::SearchCategory;
+use super::weapons::WeaponInfo;
+use crate::widgets::table_view::*;
+use imgui::*;
+use std::collections::{BTreeMap, VecDeque};
+
+pub const LOADOUT_PANEL_WIDTH: f32 = 280f32;
+
+pub const ARMOR_TYPES: [ArmorType; 5] = [
+    ArmorType::Head,
+    ArmorType::Chest,
+    ArmorType::Gloves,
+    ArmorType::Waist,
+    ArmorType::Legs,
+];
+
+//
+// Equipped
+//
+// A piece of equipment along with the decoration ids slotted into it, one entry per slot
+#[derive(Debug)]
+pub struct Equipped<T> {
+    pub item: T,
+    pub decorations: Vec<Option<i32>>,
+}
+
+impl<T> Equipped<T> {
+    pub fn new(item: T, slot_count: usize) -> Self {
+        Self {
+            item,
+            decorations: vec![None; slot_count],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EquippedCharm {
+    pub charm: CharmInfo,
+    pub rank: i32, // index into charm.ranks
+}
+
+//
+// Skill Total
+//
+#[derive(Debug)]
+pub struct SkillTotal {
+    pub skill: i32,
+    pub name: String,
+    pub points: i32,            // everything the loadout adds up to, before capping
+    pub max_level: Option<i32>, // None until the skill itself has been loaded
+}
+
+impl SkillTotal {
+    pub fn level(&self) -> i32 {
+        match self.max_level {
+            Some(max_level) => self.points.min(max_level),
+            None => self.points,
+        }
+    }
+
+    // Points past the skill's max level that don't do anything
+    pub fn wasted(&self) -> i32 {
+        self.points - self.level()
+    }
+}
+
+// Everything worked out from the loadout, rebuilt whenever a piece or decoration changes
+#[derive(Debug, Default)]
+struct LoadoutSummary {
+    modifiers: SkillRankModifiers,
+    damage: Option<EffectiveDamage>,
+    totals_cache: SimpleTableDataModel,
+}
+
+// Picks the skills the damage math needs out of the totals
+fn damage_skills(totals: &[SkillTotal]) -> DamageSkills {
+    let level = |name: &str| {
+        totals
+            .iter()
+            .find(|total| total.name == name)
+            .map_or(0, SkillTotal::level)
+    };
+    DamageSkills {
+        handicraft: level("Handicraft"),
+        critical_boost: level("Critical Boost"),
+        critical_element: level("Critical Element") > 0,
+    }
+}
+
+impl LoadoutSummary {
+    fn new(loadout: &Loadout, catalog: &Catalog) -> Self {
+        let totals = loadout.skill_totals(catalog);
+
+        let mut modifiers = SkillRankModifiers::default();
+        for total in &totals {
+            let rank = catalog
+                .skill(total.skill)
+                .and_then(|skill| skill.ranks.iter().find(|rank| rank.level == total.level()));
+            if let Some(rank) = rank {
+                modifiers.add(&rank.modifiers);
+            }
+        }
+
+        let damage = loadout
+            .weapon
+            .as_ref()
+            .map(|weapon| weapon_damage(&weapon.item, &modifiers, &damage_skills(&totals)));
+
+        let mut totals_cache = SimpleTableDataModel::new(3);
+        for total in &totals {
+            let link = EntryRequest::new(SearchCategory::Skills, total.skill);
+            totals_cache.push_link(total.name.clone(), link);
+            totals_cache.push(match total.max_level {
+                Some(max_level) => format!("Lv {}/{}", total.level(), max_level),
+                None => format!("Lv {}", total.level()),
+            });
+            totals_cache.push(match total.wasted() {
+                0 => String::new(),
+                wasted => format!("+{} wasted", wasted),
+            });
+        }
+
+        Self {
+            modifiers,
+            damage,
+            totals_cache,
+        }
+    }
+}
+
+//
+// Loadout
+//
+#[derive(Debug, Default)]
+pub struct Loadout {
+    pub weapon: Option<Equipped<WeaponInfo>>,
+    pub armor: BTreeMap<ArmorType, Equipped<ArmorInfo>>,
+    pub charm: Option<EquippedCharm>,
+
+    // internal details
+    summary: Option<LoadoutSummary>,
+}
+
+impl Loadout {
+    // Puts a loaded entry into whichever slot it belongs in, replacing what was there
+    pub fn equip(&mut self, state: EntryDisplayState) {
+        match state {
+            EntryDisplayState::Weapon(weapon) => {
+                let slot_count = weapon.slots.len();
+                self.weapon = Some(Equipped::new(weapon, slot_count));
+            }
+            EntryDisplayState::Armor(armor) => {
+                let slot_count = armor.slots.len();
+                self.armor
+                    .insert(armor.type_val, Equipped::new(armor, slot_count));
+            }
+            EntryDisplayState::Charm(charm) => {
+                let rank = charm.ranks.len().saturating_sub(1) as i32;
+                self.charm = Some(EquippedCharm { charm, rank });
+            }
+            _ => {}
+        }
+        self.summary = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weapon.is_none() && self.armor.is_empty() && self.charm.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    // Skill caps, set bonuses and decoration skills come from the catalog, so a summary worked
+    // out before it changed is stale
+    pub fn catalog_changed(&mut self) {
+        self.summary = None;
+    }
+
+    // Every skill rank the loadout provides: armor, slotted decorations, the charm and set bonuses
+    fn skill_ranks<'a>(&'a self, catalog: &'a Catalog) -> Vec<&'a SkillRank> {
+        let mut ranks: Vec<&SkillRank> = vec![];
+
+        let mut slotted = vec![];
+        if let Some(weapon) = &self.weapon {
+            slotted.extend(weapon.decorations.iter().filter_map(|deco| *deco));
+        }
+        let mut set_pieces: BTreeMap<i32, i32> = BTreeMap::new();
+        for equipped in self.armor.values() {
+            ranks.extend(equipped.item.skills.iter());
+            slotted.extend(equipped.decorations.iter().filter_map(|deco| *deco));
+            if let Some(set) = &equipped.item.armor_set {
+                *set_pieces.entry(set.id).or_insert(0) += 1;
+            }
+        }
+        for id in slotted {
+            if let Some(deco) = catalog.decoration(id) {
+                ranks.extend(deco.skills.iter());
+            }
+        }
+
+        if let Some(equipped) = &self.charm {
+            if let Some(rank) = equipped.charm.ranks.get(equipped.rank as usize) {
+                ranks.extend(rank.skills.iter());
+            }
+        }
+
+        for (set_id, count) in set_pieces {
+            let bonus = catalog.armor_set(set_id).and_then(|set| set.bonus.as_ref());
+            if let Some(bonus) = bonus {
+                ranks.extend(
+                    bonus
+                        .ranks
+                        .iter()
+                        .filter(|rank| rank.pieces <= count)
+                        .map(|rank| &rank.skill),
+                );
+            }
+        }
+
+        ranks
+    }
+
+    // Skill levels summed across the loadout, highest first
+    pub fn skill_totals(&self, catalog: &Catalog) -> Vec<SkillTotal> {
+        let mut totals: BTreeMap<i32, SkillTotal> = BTreeMap::new();
+        for rank in self.skill_ranks(catalog) {
+            let total = totals.entry(rank.skill).or_insert_with(|| SkillTotal {
+                skill: rank.skill,
+                name: rank.skill_name.clone(),
+                points: 0,
+                max_level: catalog.skill(rank.skill).map(|skill| skill.max_level()),
+            });
+            total.points += rank.level;
+        }
+
+        let mut totals = totals.into_values().collect::<Vec<_>>();
+        totals.sort_by(|a, b| b.level().cmp(&a.level()).then(a.name.cmp(&b.name)));
+        totals
+    }
+
+    // Returns true if the catalog collections that failed should be asked for again
+    fn layout_summary<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        catalog: &Catalog,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) -> bool {
+        // set bonuses and skill caps come from the catalog, so wait until it's settled
+        if catalog.decorations.is_pending()
+            || catalog.skills.is_pending()
+            || catalog.armor_sets.is_pending()
+        {
+            ui.text("Loading skill data...");
+            return false;
+        }
+        // without all of it the totals would quietly miss caps or set bonuses
+        if catalog.decorations.get().is_none()
+            || catalog.skills.get().is_none()
+            || catalog.armor_sets.get().is_none()
+        {
+            ui.text("Skill data failed to load");
+            ui.same_line(0.0);
+            return ui.small_button(im_str!("Retry"));
+        }
+
+        if self.summary.is_none() {
+            self.summary = Some(LoadoutSummary::new(self, catalog));
+        }
+        let summary = match &self.summary {
+            Some(summary) => summary,
+            None => return false,
+        };
+
+        draw_table(ui, "Skills", &summary.totals_cache, event_queue);
+
+        ui.with_font(FONT_IDX_WINDOW_TITLE, || {
+            ui.text(im_str!("Effects:"));
+        });
+        let effects = summary.modifiers.summary();
+        if effects.is_empty() {
+            ui.text("<none>");
+        } else {
+            ui.text_wrapped(&ImString::new(effects));
+        }
+
+        if let Some(damage) = &summary.damage {
+            ui.with_font(FONT_IDX_WINDOW_TITLE, || {
+                ui.text(im_str!("Damage:"));
+            });
+            match damage.sharpness {
+                Some(sharpness) => ui.text(format!(
+                    "{} sharpness, {:+}% affinity",
+                    sharpness, damage.affinity
+                )),
+                None => ui.text(format!("{:+}% affinity", damage.affinity)),
+            }
+            ui.text(format!("Effective Raw: {:.1}", damage.raw));
+            for (element, value) in &damage.elements {
+                ui.text(format!("Effective {}: {:.1}", element, value));
+            }
+        }
+        false
+    }
+}
+
+//
+// Loadout Panel
+//
+// What a row of the panel asked for; applied once drawing is done
+enum PieceAction {
+    Open(EntryRequest),
+    Unequip,
+    Slotted, // a decoration was picked or cleared
+    Retry,   // the decorations failed to load and should be asked for again
+}
+
+fn decoration_label(slot: &Slot, decoration: Option<i32>, catalog: &Catalog) -> ImString {
+    let name = match decoration {
+        Some(id) => match catalog.decoration(id) {
+            Some(deco) => deco.name.as_str(),
+            None => "...",
+        },
+        None => "-",
+    };
+    ImString::from(format!("{} {}", slot_label(Some(slot)), name))
+}
+
+// Draws the equipped piece's name and slots; returns what the user asked to do with it
+fn layout_piece<'a>(
+    ui: &Ui<'a>,
+    label: &str,
+    name: &str,
+    request: EntryRequest,
+    slots: &[Slot],
+    decorations: &mut [Option<i32>],
+    catalog: &Catalog,
+) -> Option<PieceAction> {
+    let mut action = None;
+
+    ui.text(label);
+    ui.same_line(0.0);
+    if ui.small_button(&ImString::new(name)) {
+        action = Some(PieceAction::Open(request));
+    }
+    ui.same_line(0.0);
+    if ui.small_button(im_str!("x")) {
+        action = Some(PieceAction::Unequip);
+    }
+
+    ui.indent(10.0);
+    for (idx, slot) in slots.iter().enumerate() {
+        ui.with_id(idx as i32, || {
+            let label = decoration_label(slot, decorations[idx], catalog);
+            match slot_button(ui, &label, slot, &catalog.decorations, true) {
+                Some(SlotPick::Decoration(id)) => {
+                    decorations[idx] = Some(id);
+                    action = Some(PieceAction::Slotted);
+                }
+                Some(SlotPick::Clear) => {
+                    decorations[idx] = None;
+                    action = Some(PieceAction::Slotted);
+                }
+                Some(SlotPick::Retry) => action = Some(PieceAction::Retry),
+                None => {}
+            }
+        });
+    }
+    ui.unindent(10.0);
+
+    action
+}
+
+impl MhwGui for Loadout {
+    fn layout<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        let logical_size = ui.frame_size().logical_size;
+        let draw_cursor_pos = details.next_start_pos;
+        details.next_start_pos.0 += LOADOUT_PANEL_WIDTH;
+
+        // the slot pickers need every decoration on hand, and the skill totals need the rest
+        let catalog = &mut details.catalog;
+        catalog
+            .decorations
+            .get_or_request(event_queue, MhwEvent::LoadDecorations);
+        catalog
+            .skills
+            .get_or_request(event_queue, MhwEvent::LoadSkills);
+        catalog
+            .armor_sets
+            .get_or_request(event_queue, MhwEvent::LoadArmorSets);
+        let catalog = &details.catalog;
+        let mut retry = false;
+
+        let window = ui
+            .window(im_str!("Loadout"))
+            .position(draw_cursor_pos, ImGuiCond::Always)
+            .size(
+                (
+                    LOADOUT_PANEL_WIDTH,
+                    logical_size.1 as f32 - draw_cursor_pos.1,
+                ),
+                ImGuiCond::Always,
+            )
+            .flags(ImGuiWindowFlags::NoCollapse | ImGuiWindowFlags::NoResize);
+
+        window.build(|| {
+            ui.with_font(FONT_IDX_NORMAL, || {
+                // Weapon
+                let mut weapon_action = None;
+                match &mut self.weapon {
+                    Some(equipped) => ui.with_id("weapon", || {
+                        let weapon = &equipped.item;
+                        weapon_action = layout_piece(
+                            ui,
+                            "Weapon:",
+                            weapon.name.as_str(),
+                            EntryRequest::new(SearchCategory::Weapons, weapon.id),
+                            &weapon.slots,
+                            &mut equipped.decorations,
+                            catalog,
+                        );
+                    }),
+                    None => ui.text("Weapon: <empty>"),
+                }
+                match weapon_action {
+                    Some(PieceAction::Open(request)) => {
+                        event_queue.push_back(MhwEvent::RequestEntry(request))
+                    }
+                    Some(PieceAction::Unequip) => {
+                        self.weapon = None;
+                        self.summary = None;
+                    }
+                    Some(PieceAction::Slotted) => self.summary = None,
+                    Some(PieceAction::Retry) => retry = true,
+                    None => {}
+                }
+                ui.separator();
+
+                // Armor
+                for armor_type in ARMOR_TYPES.iter() {
+                    let label = format!("{}:", armor_type);
+                    let mut armor_action = None;
+                    match self.armor.get_mut(armor_type) {
+                        Some(equipped) => ui.with_id(label.as_str(), || {
+                            let armor = &equipped.item;
+                            armor_action = layout_piece(
+                                ui,
+                                label.as_str(),
+                                armor.name.as_str(),
+                                EntryRequest::new(SearchCategory::Armor, armor.id),
+                                &armor.slots,
+                                &mut equipped.decorations,
+                                catalog,
+                            );
+                        }),
+                        None => ui.text(format!("{} <empty>", label)),
+                    }
+                    match armor_action {
+                        Some(PieceAction::Open(request)) => {
+                            event_queue.push_back(MhwEvent::RequestEntry(request))
+                        }
+                        Some(PieceAction::Unequip) => {
+                            self.armor.remove(armor_type);
+                            self.summary = None;
+                        }
+                        Some(PieceAction::Slotted) => self.summary = None,
+                        Some(PieceAction::Retry) => retry = true,
+                        None => {}
+                    }
+                }
+                ui.separator();
+
+                // Charm
+                let mut unequip_charm = false;
+                match &mut self.charm {
+                    Some(equipped) => {
+                        ui.text("Charm:");
+                        ui.same_line(0.0);
+                        let name = ImString::new(equipped.charm.name.as_str());
+                        if ui.small_button(&name) {
+                            let request =
+                                EntryRequest::new(SearchCategory::Charms, equipped.charm.id);
+                            event_queue.push_back(MhwEvent::RequestEntry(request));
+                        }
+                        ui.same_line(0.0);
+                        if ui.small_button(im_str!("x##charm")) {
+                            unequip_charm = true;
+                        }
+
+                        let rank_names = equipped
+                            .charm
+                            .ranks
+                            .iter()
+                            .map(|rank| ImString::new(rank.name.as_str()))
+                            .collect::<Vec<_>>();
+                        let ref_names = rank_names
+                            .iter()
+                            .map(std::convert::AsRef::as_ref)
+                            .collect::<Vec<_>>();
+                        ui.indent(10.0);
+                        let mut rank_changed = false;
+                        ui.with_item_width(-1.0, || {
+                            rank_changed = ui.combo(
+                                im_str!("##charm_rank"),
+                                &mut equipped.rank,
+                                ref_names.as_slice(),
+                                ref_names.len() as i32,
+                            );
+                        });
+                        if rank_changed {
+                            self.summary = None;
+                        }
+                        ui.unindent(10.0);
+                    }
+                    None => ui.text("Charm: <empty>"),
+                }
+                if unequip_charm {
+                    self.charm = None;
+                    self.summary = None;
+                }
+
+                if !self.is_empty() {
+                    ui.separator();
+                    retry |= self.layout_summary(ui, catalog, event_queue);
+                }
+
+                if !self.is_empty() {
+                    ui.separator();
+                    if ui.button(im_str!("Clear Loadout"), (0.0, 0.0)) {
+                        self.clear();
+                    }
+                }
+            });
+        });
+
+        // only the failed collections start over
+        if retry {
+            let catalog = &mut details.catalog;
+            catalog.decorations.retry();
+            catalog.skills.retry();
+            catalog.armor_sets.retry();
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use super::common::{fonts::*, Element, GuiDetails, MhwEvent, MhwWindowContents};
+use super::entry_display::EntryRequest;
+use super::items::ItemInfo;
+use super::scraping::ScrapedMonster;
+use super::search::SearchCategory;
+use crate::widgets::table_view::*;
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum MonsterType {
+    Large,
+    Small,
+}
+
+impl Display for MonsterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AilmentSkill {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AilmentRecovery {
+    pub actions: Vec<String>,
+    pub items: Vec<ItemInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AilmentProtection {
+    pub skills: Vec<AilmentSkill>,
+    pub items: Vec<ItemInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Ailment {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub recovery: AilmentRecovery,
+    #[serde(default)]
+    pub protection: AilmentProtection,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonsterLocation {
+    pub id: i32,
+    pub name: String,
+    pub zone_count: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonsterResistance {
+    pub element: Element,
+    pub condition: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonsterWeakness {
+    pub element: Element,
+    pub stars: i32, // 1 to 3, higher is more effective
+    pub condition: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardCondition {
+    #[serde(rename = "type")]
+    pub type_val: String, // carve, investigation, tracking, etc.
+    pub subtype: Option<String>,
+    pub rank: String,
+    pub quantity: i32,
+    pub chance: i32, // percent
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonsterReward {
+    pub id: i32,
+    pub item: ItemInfo,
+    pub conditions: Vec<RewardCondition>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonsterInfo {
+    pub id: i32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_val: MonsterType,
+    pub species: String,
+    #[serde(default)]
+    pub description: String,
+    pub elements: Vec<Element>,
+    pub ailments: Vec<Ailment>,
+    pub locations: Vec<MonsterLocation>,
+    pub resistances: Vec<MonsterResistance>,
+    pub weaknesses: Vec<MonsterWeakness>,
+    pub rewards: Vec<MonsterReward>,
+
+    // internal details
+    #[serde(skip)]
+    weaknesses_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    resistances_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    ailments_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    locations_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    rewards_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    pub kiranico: KiranicoDetails,
+    #[serde(skip)]
+    hitzones_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    thresholds_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    drop_rates_cache: SimpleTableDataModel,
+}
+
+//
+// Kiranico Details
+//
+// Hitzones, part thresholds and drop rates, which only kiranico has; fetched when asked for
+#[derive(Debug, Default)]
+pub enum KiranicoDetails {
+    #[default]
+    NotLoaded,
+    Loading,
+    Loaded(ScrapedMonster),
+    Failed,
+}
+
+// Title-cases the lowercase names the API uses, e.g. "flying wyvern" -> "Flying Wyvern"
+pub fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl MonsterInfo {
+    fn weaknesses_data(&mut self) -> &TableDataModel {
+        if self.weaknesses_cache.is_empty() {
+            self.weaknesses_cache.set_columns(3);
+            let weaknesses = &self.weaknesses;
+            for weakness in weaknesses {
+                self.weaknesses_cache.push(weakness.element.to_string());
+                self.weaknesses_cache
+                    .push("*".repeat(weakness.stars.max(0) as usize));
+                self.weaknesses_cache
+                    .push(weakness.condition.clone().unwrap_or_default());
+            }
+        }
+        &self.weaknesses_cache
+    }
+
+    fn resistances_data(&mut self) -> &TableDataModel {
+        if self.resistances_cache.is_empty() {
+            self.resistances_cache.set_columns(2);
+            let resistances = &self.resistances;
+            for resistance in resistances {
+                self.resistances_cache.push(resistance.element.to_string());
+                self.resistances_cache
+                    .push(resistance.condition.clone().unwrap_or_default());
+            }
+        }
+        &self.resistances_cache
+    }
+
+    fn ailments_data(&mut self) -> &TableDataModel {
+        if self.ailments_cache.is_empty() {
+            self.ailments_cache.set_columns(2);
+            let ailments = &self.ailments;
+            for ailment in ailments {
+                let protection = ailment
+                    .protection
+                    .skills
+                    .iter()
+                    .map(|skill| skill.name.as_str())
+                    .chain(
+                        ailment
+                            .protection
+                            .items
+                            .iter()
+                            .map(|item| item.name.as_str()),
+                    )
+                    .collect::<Vec<_>>();
+                self.ailments_cache.push(ailment.name.clone());
+                self.ailments_cache.push(protection.join(", "));
+            }
+        }
+        &self.ailments_cache
+    }
+
+    fn locations_data(&mut self) -> &TableDataModel {
+        if self.locations_cache.is_empty() {
+            self.locations_cache.set_columns(2);
+            let locations = &self.locations;
+            for location in locations {
+                self.locations_cache.push(location.name.clone());
+                self.locations_cache
+                    .push(format!("{} zones", location.zone_count));
+            }
+        }
+        &self.locations_cache
+    }
+
+    fn rewards_data(&mut self) -> &TableDataModel {
+        if self.rewards_cache.is_empty() {
+            self.rewards_cache.set_columns(5);
+            let rewards = &self.rewards;
+            for reward in rewards {
+                for condition in &reward.conditions {
+                    let method = match &condition.subtype {
+                        Some(subtype) => format!("{} ({})", condition.type_val, subtype),
+                        None => condition.type_val.clone(),
+                    };
+                    let link = EntryRequest::new(SearchCategory::Items, reward.item.id);
+                    self.rewards_cache.push_link(reward.item.name.clone(), link);
+                    self.rewards_cache.push(title_case(condition.rank.as_str()));
+                    self.rewards_cache.push(title_case(method.as_str()));
+                    self.rewards_cache.push(format!("x{}", condition.quantity));
+                    self.rewards_cache.push(format!("{}%", condition.chance));
+                }
+            }
+        }
+        &self.rewards_cache
+    }
+
+    fn hitzones_data(&mut self) -> &TableDataModel {
+        if self.hitzones_cache.is_empty() {
+            if let KiranicoDetails::Loaded(scraped) = &self.kiranico {
+                self.hitzones_cache.set_columns(10);
+                self.hitzones_cache.append(
+                    [
+                        "Part", "Sever", "Blunt", "Shot", "Fire", "Water", "Thunder", "Ice",
+                        "Dragon", "Stun",
+                    ]
+                    .iter()
+                    .map(|header| (*header).to_owned())
+                    .collect(),
+                );
+                for hitzone in &scraped.hitzones {
+                    self.hitzones_cache.push(hitzone.part.clone());
+                    let values = [
+                        hitzone.sever,
+                        hitzone.blunt,
+                        hitzone.shot,
+                        hitzone.fire,
+                        hitzone.water,
+                        hitzone.thunder,
+                        hitzone.ice,
+                        hitzone.dragon,
+                        hitzone.stun,
+                    ];
+                    for value in values.iter() {
+                        self.hitzones_cache.push(value.to_string());
+                    }
+                }
+            }
+        }
+        &self.hitzones_cache
+    }
+
+    fn thresholds_data(&mut self) -> &TableDataModel {
+        if self.thresholds_cache.is_empty() {
+            if let KiranicoDetails::Loaded(scraped) = &self.kiranico {
+                let value = |value: Option<i32>| value.map_or("-".to_owned(), |v| v.to_string());
+                self.thresholds_cache.set_columns(4);
+                for header in ["Part", "Stagger", "Break", "Sever"].iter() {
+                    self.thresholds_cache.push((*header).to_owned());
+                }
+                for thresholds in &scraped.thresholds {
+                    self.thresholds_cache.push(thresholds.part.clone());
+                    self.thresholds_cache.push(value(thresholds.stagger));
+                    self.thresholds_cache.push(value(thresholds.break_value));
+                    self.thresholds_cache.push(value(thresholds.sever));
+                }
+            }
+        }
+        &self.thresholds_cache
+    }
+
+    fn drop_rates_data(&mut self) -> &TableDataModel {
+        if self.drop_rates_cache.is_empty() {
+            if let KiranicoDetails::Loaded(scraped) = &self.kiranico {
+                self.drop_rates_cache.set_columns(5);
+                for drop in &scraped.rewards {
+                    self.drop_rates_cache.push(drop.item.clone());
+                    self.drop_rates_cache.push(format!("{:?}", drop.rank));
+                    self.drop_rates_cache.push(drop.condition.clone());
+                    self.drop_rates_cache.push(format!("x{}", drop.quantity));
+                    self.drop_rates_cache.push(format!("{}%", drop.chance));
+                }
+            }
+        }
+        &self.drop_rates_cache
+    }
+
+    fn request_kiranico(&mut self, event_queue: &mut VecDeque<MhwEvent>) {
+        self.kiranico = KiranicoDetails::Loading;
+        event_queue.push_back(MhwEvent::LoadKiranico(self.id, self.name.clone()));
+    }
+}
+
+impl MhwWindowContents for MonsterInfo {
+    fn build_window<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        _details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        //=======================================
+        // Name/ID
+        ui.with_font(FONT_IDX_HEADER, || {
+            let imstring = ImString::new(self.name.as_str());
+            ui.text(&imstring);
+        });
+        ui.with_font(FONT_IDX_MINI, || {
+            let id_string = format!("id: [{}]", self.id);
+            ui.text(id_string.as_str());
+            ui.same_line(0.0);
+            ui.text(format!(
+                "{} {}",
+                self.type_val,
+                title_case(self.species.as_str())
+            ));
+        });
+
+        ui.columns(2, im_str!("monster_misc"), true);
+        // Description
+        ui.with_font(FONT_IDX_WINDOW_TITLE, || {
+            let text = im_str!("Description:");
+            ui.text(text);
+        });
+        ui.with_font(FONT_IDX_NORMAL, || {
+            let imstring = ImString::new(self.description.as_str());
+            ui.text_wrapped(&imstring);
+        });
+
+        // Elements
+        ui.next_column();
+        ui.with_font(FONT_IDX_WINDOW_TITLE, || {
+            let text = im_str!("Elements");
+            ui.text(text);
+        });
+        ui.with_font(FONT_IDX_NORMAL, || {
+            if self.elements.is_empty() {
+                ui.text("<none>");
+            }
+            for elem in &self.elements {
+                ui.text(elem.to_string());
+            }
+        });
+
+        //=======================================
+        // Lists section
+        ui.columns(2, im_str!("monster_attribs"), true);
+        ui.separator();
+        draw_table(ui, "Weaknesses", self.weaknesses_data(), event_queue);
+
+        ui.next_column();
+        draw_table(ui, "Resistances", self.resistances_data(), event_queue);
+
+        ui.next_column();
+        draw_table(ui, "Ailments", self.ailments_data(), event_queue);
+
+        ui.next_column();
+        draw_table(ui, "Locations", self.locations_data(), event_queue);
+
+        ui.columns(1, im_str!("monster_rewards"), false);
+        draw_table(ui, "Rewards", self.rewards_data(), event_queue);
+
+        //=======================================
+        // Kiranico section
+        ui.separator();
+        match self.kiranico {
+            KiranicoDetails::NotLoaded => {
+                if ui.small_button(im_str!("Load hitzones from Kiranico")) {
+                    self.request_kiranico(event_queue);
+                }
+            }
+            KiranicoDetails::Loading => ui.text("Loading hitzones..."),
+            KiranicoDetails::Failed => {
+                ui.text("Loading hitzones failed");
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Retry")) {
+                    self.request_kiranico(event_queue);
+                }
+            }
+            KiranicoDetails::Loaded(_) => {
+                draw_table(ui, "Hitzones", self.hitzones_data(), event_queue);
+                draw_table(ui, "Part Thresholds", self.thresholds_data(), event_queue);
+                draw_table(ui, "Drop Rates", self.drop_rates_data(), event_queue);
+            }
+        }
+    }
+}
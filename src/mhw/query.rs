@@ -1,27 +1,111 @@
+use super::data_source::DataSource;
 use super::search::SearchCategory;
 use itertools::Itertools;
 use reqwest;
-use reqwest::Url;
 use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+use serde_path_to_error;
 use std::fmt;
 use urlencoding;
 
+//
+// Query Value
+//
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryValue {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl fmt::Display for QueryValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryValue::Number(val) => write!(f, "{}", val),
+            QueryValue::Text(val) => write!(f, "{}", encode_text(val)?),
+            QueryValue::Bool(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+// Quotes and escapes `val` as a JSON string, then makes that safe to put in a URL
+fn encode_text(val: &str) -> Result<String, fmt::Error> {
+    let json = serde_json::to_string(val).map_err(|_| fmt::Error)?;
+    Ok(urlencoding::encode(&json))
+}
+
+impl From<i32> for QueryValue {
+    fn from(val: i32) -> Self {
+        QueryValue::Number(f64::from(val))
+    }
+}
+
+impl From<u32> for QueryValue {
+    fn from(val: u32) -> Self {
+        QueryValue::Number(f64::from(val))
+    }
+}
+
+impl From<f64> for QueryValue {
+    fn from(val: f64) -> Self {
+        QueryValue::Number(val)
+    }
+}
+
+impl From<bool> for QueryValue {
+    fn from(val: bool) -> Self {
+        QueryValue::Bool(val)
+    }
+}
+
+impl From<&str> for QueryValue {
+    fn from(val: &str) -> Self {
+        QueryValue::Text(val.to_owned())
+    }
+}
+
+impl From<String> for QueryValue {
+    fn from(val: String) -> Self {
+        QueryValue::Text(val)
+    }
+}
+
 //
 // Query Filter Type
 //
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum QueryFilterType {
-    Exact(String),
+    Exact(QueryValue),
     Like(String),
+    NotEqual(QueryValue),
+    GreaterThan(QueryValue),
+    GreaterThanOrEqual(QueryValue),
+    LessThan(QueryValue),
+    LessThanOrEqual(QueryValue),
+    Between(QueryValue, QueryValue), // inclusive at both ends
+    In(Vec<QueryValue>),
+    NotIn(Vec<QueryValue>),
+    Exists(bool),
 }
 
 impl fmt::Display for QueryFilterType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            QueryFilterType::Exact(val) => write!(f, "{}", urlencoding::encode(val)),
-            QueryFilterType::Like(val) => {
-                write!(f, "{{\"$like\":\"{}\"}}", urlencoding::encode(val))
+            QueryFilterType::Exact(val) => write!(f, "{}", val),
+            QueryFilterType::Like(val) => write!(f, "{{\"$like\":{}}}", encode_text(val)?),
+            QueryFilterType::NotEqual(val) => write!(f, "{{\"$ne\":{}}}", val),
+            QueryFilterType::GreaterThan(val) => write!(f, "{{\"$gt\":{}}}", val),
+            QueryFilterType::GreaterThanOrEqual(val) => write!(f, "{{\"$gte\":{}}}", val),
+            QueryFilterType::LessThan(val) => write!(f, "{{\"$lt\":{}}}", val),
+            QueryFilterType::LessThanOrEqual(val) => write!(f, "{{\"$lte\":{}}}", val),
+            QueryFilterType::Between(min, max) => {
+                write!(f, "{{\"$gte\":{},\"$lte\":{}}}", min, max)
+            }
+            QueryFilterType::In(vals) => write!(f, "{{\"$in\":[{}]}}", vals.iter().format(",")),
+            QueryFilterType::NotIn(vals) => {
+                write!(f, "{{\"$nin\":[{}]}}", vals.iter().format(","))
             }
+            QueryFilterType::Exists(val) => write!(f, "{{\"$exists\":{}}}", val),
         }
     }
 }
@@ -29,81 +113,109 @@ impl fmt::Display for QueryFilterType {
 //
 // Query Filter
 //
-#[derive(Debug)]
-pub struct QueryFilter {
-    field_name: String,
-    filter: QueryFilterType,
+#[derive(Debug, Clone)]
+pub enum QueryFilter {
+    Field {
+        field_name: String,
+        filter: QueryFilterType,
+    },
+    And(Vec<QueryFilter>),
+    Or(Vec<QueryFilter>),
 }
 
 impl QueryFilter {
     pub fn new(field: String, filter: QueryFilterType) -> Self {
-        Self {
+        QueryFilter::Field {
             field_name: field,
-            filter: filter,
+            filter,
         }
     }
-}
 
-impl fmt::Display for QueryFilter {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{{\"{name}\":{filter}}}",
-            name = self.field_name,
-            filter = self.filter
-        )
+    pub fn all(filters: Vec<QueryFilter>) -> Self {
+        QueryFilter::And(filters)
     }
-}
 
-//
-// Query Prediction Meta
-//
-#[derive(Debug)]
-#[allow(dead_code)]
-pub enum QueryProjectionMeta {
-    Inclusive,
-    Exclusive,
+    pub fn any(filters: Vec<QueryFilter>) -> Self {
+        QueryFilter::Or(filters)
+    }
 }
 
-impl Default for QueryProjectionMeta {
-    fn default() -> Self {
-        QueryProjectionMeta::Inclusive
+impl fmt::Display for QueryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryFilter::Field { field_name, filter } => write!(
+                f,
+                "{{\"{name}\":{filter}}}",
+                name = field_name,
+                filter = filter
+            ),
+            QueryFilter::And(filters) => {
+                write!(f, "{{\"$and\":[{}]}}", filters.iter().format(","))
+            }
+            QueryFilter::Or(filters) => write!(f, "{{\"$or\":[{}]}}", filters.iter().format(",")),
+        }
     }
 }
 
 //
 // Query Prediction
 //
+// Only the listed fields are sent back; mhw-db can also exclude fields, but nothing here needs to
 #[derive(Debug, Default)]
 pub struct QueryProjection {
-    meta: QueryProjectionMeta,
     fields: Vec<&'static str>,
 }
 
+impl QueryProjection {
+    // Only `fields` are sent back
+    pub fn including(fields: Vec<&'static str>) -> Self {
+        Self { fields }
+    }
+
+    pub fn fields(&self) -> &[&'static str] {
+        &self.fields
+    }
+}
+
 impl fmt::Display for QueryProjection {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let bool_str = match self.meta {
-            QueryProjectionMeta::Inclusive => "true",
-            QueryProjectionMeta::Exclusive => "false",
-        };
-
         write!(
             f,
             "{{{}}}",
             self.fields
                 .iter()
-                .format_with(",", |data, f| f(&format_args!("\"{}\":{}", data, bool_str)))
+                .format_with(",", |data, f| f(&format_args!("\"{}\":true", data)))
         )
     }
 }
 
+//
+// Query Combinator
+//
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QueryCombinator {
+    #[default]
+    All,
+    Any,
+}
+
+impl QueryCombinator {
+    // Groups the filters so that either all of them or just one has to match
+    pub fn combine(self, filters: Vec<QueryFilter>) -> QueryFilter {
+        match self {
+            QueryCombinator::All => QueryFilter::all(filters),
+            QueryCombinator::Any => QueryFilter::any(filters),
+        }
+    }
+}
+
 //
 // Query Info
 //
 #[derive(Debug)]
 pub struct QueryInfo {
     category: SearchCategory,
-    filter: Option<QueryFilter>,
+    filters: Vec<QueryFilter>, // every one of them has to match
     projection: Option<QueryProjection>,
 }
 
@@ -125,48 +237,54 @@ impl QueryInfo {
 
         Self {
             category,
-            filter: Some(QueryFilter {
-                field_name: "name".to_owned(),
-                filter: QueryFilterType::Like(search_string),
-            }),
-            projection: Some(QueryProjection {
-                meta: QueryProjectionMeta::Inclusive,
-                fields: vec!["id", "name", "type"],
-            }),
+            filters: vec![QueryFilter::new(
+                "name".to_owned(),
+                QueryFilterType::Like(search_string),
+            )],
+            projection: Some(QueryProjection::including(vec!["id", "name", "type"])),
         }
     }
 
     pub fn find_category(category: SearchCategory) -> Self {
         Self {
-            category: category,
-            filter: Default::default(),
+            category,
+            filters: Default::default(),
             projection: Default::default(),
         }
     }
 
     pub fn with_filter(mut self, filter: QueryFilter) -> Self {
-        self.filter = Some(filter);
+        self.filters.push(filter);
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_projection(mut self, proj: QueryProjection) -> Self {
         self.projection = Some(proj);
         self
     }
 
-    pub fn execute_mhw_query<T>(&self) -> Result<T, MHWQueryError>
-    where
-        T: DeserializeOwned,
-    {
+    pub fn category(&self) -> SearchCategory {
+        self.category
+    }
+
+    pub fn filters(&self) -> &[QueryFilter] {
+        &self.filters
+    }
+
+    pub fn projection(&self) -> Option<&QueryProjection> {
+        self.projection.as_ref()
+    }
+
+    pub fn url_string(&self, base_url: &str) -> String {
         let mut url_string = format!(
-            "https://mhw-db.com/{category}",
-            category = self.category.to_string()
+            "{base}/{category}",
+            base = base_url,
+            category = self.category
         );
 
         let mut prefix = "?"; // in case there's no query, prefix should use '?'
-        if let Some(filter) = &self.filter {
-            let filter_string = format!("?q={{\"$and\": [{}] }}", filter);
+        if !self.filters.is_empty() {
+            let filter_string = format!("?q={{\"$and\": [{}] }}", self.filters.iter().format(","));
             url_string.push_str(filter_string.as_str());
             prefix = "&";
         }
@@ -176,20 +294,28 @@ impl QueryInfo {
             url_string.push_str(projection_string.as_str());
         }
 
-        let url = Url::parse(url_string.as_str()).unwrap();
-        println!("{}", url.as_str());
+        url_string
+    }
 
-        let mut result = match reqwest::get(url) {
-            Ok(r) => r,
-            Err(e) => return Err(MHWQueryError::API(format!("Error querying API: {}", e))),
-        };
+    pub fn execute<T>(&self, source: &dyn DataSource) -> Result<T, MHWQueryError>
+    where
+        T: DeserializeOwned,
+    {
+        let found = source.fetch(self)?;
+        decode_value(found)
+    }
+}
 
-        match result.json() {
-            Ok(r) => Ok(r),
-            Err(e) => Err(MHWQueryError::Internal(format!(
-                "Error converting API search into Vec: {}",
-                e
-            ))),
+// Converts a JSON value into `T`, reporting where in the document decoding went wrong
+pub fn decode_value<T>(value: Value) -> Result<T, MHWQueryError>
+where
+    T: DeserializeOwned,
+{
+    match serde_path_to_error::deserialize(value) {
+        Ok(r) => Ok(r),
+        Err(e) => {
+            let path = e.path().to_string();
+            Err(MHWQueryError::from_decode(path, e.into_inner()))
         }
     }
 }
@@ -197,19 +323,207 @@ impl QueryInfo {
 //
 // MHW Query Error
 //
+#[derive(Debug)]
 pub enum MHWQueryError {
     Internal(String),
-    API(String),
-    #[allow(dead_code)]
-    Unimplemented,
+    Network(String),
+    Timeout(String),
+    HttpStatus { status: u16, body: String },
+    Decode { path: String, message: String },
+    UnknownEnumValue { path: String, value: String },
+    RateLimited { retry_after: Option<u64> }, // seconds, if the server said
+}
+
+impl MHWQueryError {
+    pub fn from_decode<E: fmt::Display>(path: String, error: E) -> Self {
+        let message = error.to_string();
+
+        // serde reports enum mismatches as "unknown variant `value`, expected ..."
+        if message.starts_with("unknown variant `") {
+            if let Some(value) = message.split('`').nth(1) {
+                return MHWQueryError::UnknownEnumValue {
+                    path,
+                    value: value.to_owned(),
+                };
+            }
+        }
+
+        MHWQueryError::Decode { path, message }
+    }
+
+    // Short heading for the error panel
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MHWQueryError::Internal(_) => "Internal Error",
+            MHWQueryError::Network(_) => "Network Error",
+            MHWQueryError::Timeout(_) => "Timed Out",
+            MHWQueryError::HttpStatus { .. } => "HTTP Error",
+            MHWQueryError::Decode { .. } => "Bad Response",
+            MHWQueryError::UnknownEnumValue { .. } => "Unknown Value",
+            MHWQueryError::RateLimited { .. } => "Rate Limited",
+        }
+    }
+}
+
+impl From<reqwest::Error> for MHWQueryError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            MHWQueryError::Timeout(e.to_string())
+        } else {
+            MHWQueryError::Network(e.to_string())
+        }
+    }
 }
 
 impl fmt::Display for MHWQueryError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            MHWQueryError::Unimplemented => write!(f, "Unimplemented!"),
             MHWQueryError::Internal(s) => write!(f, "Internal error: {}", s),
-            MHWQueryError::API(s) => write!(f, "API error: {}", s),
+            MHWQueryError::Network(s) => write!(f, "Network error: {}", s),
+            MHWQueryError::Timeout(s) => write!(f, "Request timed out: {}", s),
+            MHWQueryError::HttpStatus { status, body } => {
+                write!(f, "HTTP status {}: {}", status, body)
+            }
+            MHWQueryError::Decode { path, message } => {
+                write!(f, "Error decoding `{}`: {}", path, message)
+            }
+            MHWQueryError::UnknownEnumValue { path, value } => {
+                write!(f, "Unknown value \"{}\" at `{}`", value, path)
+            }
+            MHWQueryError::RateLimited { retry_after } => match retry_after {
+                Some(secs) => write!(f, "Rate limited, retry after {} seconds", secs),
+                None => write!(f, "Rate limited, try again later"),
+            },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://mhw-db.com";
+
+    fn field(name: &str, filter: QueryFilterType) -> QueryFilter {
+        QueryFilter::new(name.to_owned(), filter)
+    }
+
+    fn armor_url(filters: Vec<QueryFilter>) -> String {
+        let query = QueryInfo::find_category(SearchCategory::Armor);
+        filters
+            .into_iter()
+            .fold(query, |query, filter| query.with_filter(filter))
+            .url_string(BASE)
+    }
+
+    #[test]
+    fn category_without_filters() {
+        assert_eq!(armor_url(vec![]), "https://mhw-db.com/armor");
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let rarity = |filter| armor_url(vec![field("rarity", filter)]);
+        assert_eq!(
+            rarity(QueryFilterType::Exact(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":6}] }"
+        );
+        assert_eq!(
+            rarity(QueryFilterType::NotEqual(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":{\"$ne\":6}}] }"
+        );
+        assert_eq!(
+            rarity(QueryFilterType::GreaterThan(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":{\"$gt\":6}}] }"
+        );
+        assert_eq!(
+            rarity(QueryFilterType::GreaterThanOrEqual(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":{\"$gte\":6}}] }"
+        );
+        assert_eq!(
+            rarity(QueryFilterType::LessThan(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":{\"$lt\":6}}] }"
+        );
+        assert_eq!(
+            rarity(QueryFilterType::LessThanOrEqual(6.into())),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"rarity\":{\"$lte\":6}}] }"
+        );
+        assert_eq!(
+            armor_url(vec![field(
+                "defense.base",
+                QueryFilterType::Between(60.into(), 80.into())
+            )]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"defense.base\":{\"$gte\":60,\"$lte\":80}}] }"
+        );
+    }
+
+    #[test]
+    fn set_and_existence_operators() {
+        assert_eq!(
+            armor_url(vec![field(
+                "slots.rank",
+                QueryFilterType::In(vec![2.into(), 3.into()])
+            )]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"slots.rank\":{\"$in\":[2,3]}}] }"
+        );
+        assert_eq!(
+            armor_url(vec![field(
+                "type",
+                QueryFilterType::NotIn(vec!["head".into(), "legs".into()])
+            )]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"type\":{\"$nin\":[%22head%22,%22legs%22]}}] }"
+        );
+        assert_eq!(
+            armor_url(vec![field("armorSet.bonus", QueryFilterType::Exists(true))]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"armorSet.bonus\":{\"$exists\":true}}] }"
+        );
+    }
+
+    #[test]
+    fn nested_groups() {
+        let filter = QueryFilter::any(vec![
+            field("rank", QueryFilterType::Exact("high".into())),
+            QueryFilter::all(vec![
+                field("rarity", QueryFilterType::GreaterThanOrEqual(5.into())),
+                field("slots.rank", QueryFilterType::Exact(3.into())),
+            ]),
+        ]);
+        assert_eq!(
+            armor_url(vec![filter]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"$or\":[{\"rank\":%22high%22},\
+             {\"$and\":[{\"rarity\":{\"$gte\":5}},{\"slots.rank\":3}]}]}] }"
+        );
+    }
+
+    #[test]
+    fn combinators_group_filters() {
+        let rarity = |val: i32| field("rarity", QueryFilterType::Exact(val.into()));
+        assert_eq!(
+            armor_url(vec![
+                QueryCombinator::Any.combine(vec![rarity(1), rarity(2)])
+            ]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"$or\":[{\"rarity\":1},{\"rarity\":2}]}] }"
+        );
+        assert_eq!(
+            armor_url(vec![QueryCombinator::All.combine(vec![rarity(1)])]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"$and\":[{\"rarity\":1}]}] }"
+        );
+    }
+
+    #[test]
+    fn text_is_quoted_and_escaped() {
+        assert_eq!(
+            armor_url(vec![field(
+                "name",
+                QueryFilterType::Exact("Leather Headgear \"α+\"".into())
+            )]),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"name\":\
+             %22Leather%20Headgear%20%5C%22%CE%B1%2B%5C%22%22}] }"
+        );
+        assert_eq!(
+            QueryInfo::find_ids("rath*mail", SearchCategory::Armor).url_string(BASE),
+            "https://mhw-db.com/armor?q={\"$and\": [{\"name\":{\"$like\":%22%25rath%25mail%25%22}}] }\
+             &p={\"id\":true,\"name\":true,\"type\":true}"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use super::query::MHWQueryError;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_CACHE_DIR: &str = "mhw_cache";
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 60 * 60 * 24;
+
+// Set this to override how many seconds a cached response is used without revalidating
+pub const CACHE_TTL_VAR: &str = "MHW_CACHE_TTL";
+
+// FNV-1a, since std's hasher isn't guaranteed to give the same result between releases and
+// entry names have to survive rebuilds
+fn url_hash(url: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    url.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+fn now_secs() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    }
+}
+
+//
+// Cache Entry
+//
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub url: String,
+    pub fetched_at: u64, // seconds since the unix epoch
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl CacheEntry {
+    pub fn new(
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: String,
+    ) -> Self {
+        Self {
+            url: url.to_owned(),
+            fetched_at: now_secs(),
+            etag,
+            last_modified,
+            body,
+        }
+    }
+
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        now_secs().saturating_sub(self.fetched_at) < ttl.as_secs()
+    }
+
+    // Called when the server confirms the entry is still current
+    pub fn touch(&mut self) {
+        self.fetched_at = now_secs();
+    }
+}
+
+//
+// Query Cache
+//
+#[derive(Debug)]
+pub struct QueryCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl QueryCache {
+    pub fn new(dir: &Path, ttl: Duration) -> Self {
+        Self {
+            dir: dir.to_owned(),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Entries are keyed by the final query URL
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", url_hash(url)))
+    }
+
+    pub fn load(&self, url: &str) -> Option<CacheEntry> {
+        let text = fs::read_to_string(self.entry_path(url)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(text.as_str()).ok()?;
+
+        // guard against hash collisions
+        if entry.url == url {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub fn store(&self, entry: &CacheEntry) -> Result<(), MHWQueryError> {
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            return Err(MHWQueryError::Internal(format!(
+                "Unable to create cache directory: {}",
+                e
+            )));
+        }
+
+        let text = match serde_json::to_string(entry) {
+            Ok(t) => t,
+            Err(e) => {
+                return Err(MHWQueryError::Internal(format!(
+                    "Unable to serialize cache entry: {}",
+                    e
+                )))
+            }
+        };
+
+        match fs::write(self.entry_path(entry.url.as_str()), text) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(MHWQueryError::Internal(format!(
+                "Unable to write cache entry: {}",
+                e
+            ))),
+        }
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        if !self.dir.exists() {
+            return Ok(());
+        }
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_hashes_match_fnv1a() {
+        // reference values for 64-bit FNV-1a
+        assert_eq!(url_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(url_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(url_hash("foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -1,12 +1,635 @@
+use super::query::{QueryFilter, QueryFilterType, QueryValue};
 use super::search::SearchCategory;
 use imgui::*;
 
-pub fn get_filter_categories(search_category: &SearchCategory) -> Vec<ImString> {
+//
+// Filter Kind
+//
+#[derive(Debug, Clone, Copy)]
+pub enum FilterKind {
+    Choice(&'static [(&'static str, &'static str)]), // (label, API value), matched exactly
+    Keyword(&'static [(&'static str, &'static str)]), // (label, API value), exact unless it has a %
+    Present(&'static [(&'static str, &'static str)]), // (label, field suffix), matched with $exists
+    Number,
+    Count, // how many entries an array field has, e.g. how many slots
+    Range,
+    Text,
+}
+
+//
+// Filter Field
+//
+#[derive(Debug)]
+pub struct FilterField {
+    pub label: &'static str,
+    pub field_name: &'static str,
+    pub kind: FilterKind,
+}
+
+const ARMOR_RANKS: &[(&str, &str)] = &[("Low", "low"), ("High", "high")];
+
+const ARMOR_TYPES: &[(&str, &str)] = &[
+    ("Head", "head"),
+    ("Chest", "chest"),
+    ("Gloves", "gloves"),
+    ("Waist", "waist"),
+    ("Legs", "legs"),
+];
+
+const WEAPON_TYPES: &[(&str, &str)] = &[
+    ("Great Sword", "great-sword"),
+    ("Long Sword", "long-sword"),
+    ("Sword and Shield", "sword-and-shield"),
+    ("Dual Blades", "dual-blades"),
+    ("Hammer", "hammer"),
+    ("Hunting Horn", "hunting-horn"),
+    ("Lance", "lance"),
+    ("Gunlance", "gunlance"),
+    ("Switch Axe", "switch-axe"),
+    ("Charge Blade", "charge-blade"),
+    ("Insect Glaive", "insect-glaive"),
+    ("Light Bowgun", "light-bowgun"),
+    ("Heavy Bowgun", "heavy-bowgun"),
+    ("Bow", "bow"),
+];
+
+const ELEMENTS: &[(&str, &str)] = &[
+    ("Fire", "fire"),
+    ("Water", "water"),
+    ("Ice", "ice"),
+    ("Thunder", "thunder"),
+    ("Dragon", "dragon"),
+    ("Blast", "blast"),
+    ("Poison", "poison"),
+    ("Sleep", "sleep"),
+    ("Paralysis", "paralysis"),
+];
+
+const MONSTER_TYPES: &[(&str, &str)] = &[("Large", "large"), ("Small", "small")];
+
+// some types carry a strength or level after them, e.g. "dragon 300" or "normal lv2"
+const PHIAL_TYPES: &[(&str, &str)] = &[
+    ("Impact", "impact"),
+    ("Element", "element"),
+    ("Power", "power"),
+    ("Power Element", "power element"),
+    ("Dragon", "dragon %"),
+    ("Exhaust", "exhaust %"),
+    ("Paralysis", "paralysis %"),
+    ("Poison", "poison %"),
+];
+
+const SHELLING_TYPES: &[(&str, &str)] = &[
+    ("Normal", "normal %"),
+    ("Long", "long %"),
+    ("Wide", "wide %"),
+];
+
+const AMMO_TYPES: &[(&str, &str)] = &[
+    ("Normal", "normal"),
+    ("Piercing", "piercing"),
+    ("Spread", "spread"),
+    ("Sticky", "sticky"),
+    ("Cluster", "cluster"),
+    ("Recover", "recover"),
+    ("Poison", "poison"),
+    ("Paralysis", "paralysis"),
+    ("Sleep", "sleep"),
+    ("Exhaust", "exhaust"),
+    ("Flaming", "flaming"),
+    ("Water", "water"),
+    ("Freeze", "freeze"),
+    ("Thunder", "thunder"),
+    ("Dragon", "dragon"),
+    ("Slicing", "slicing"),
+    ("Wyvern", "wyvern"),
+    ("Demon", "demon"),
+    ("Armor", "armor"),
+    ("Tranq", "tranq"),
+];
+
+const ARMOR_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Rank",
+        field_name: "rank",
+        kind: FilterKind::Choice(ARMOR_RANKS),
+    },
+    FilterField {
+        label: "Type",
+        field_name: "type",
+        kind: FilterKind::Choice(ARMOR_TYPES),
+    },
+    FilterField {
+        label: "Rarity",
+        field_name: "rarity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Slot Count",
+        field_name: "slots",
+        kind: FilterKind::Count,
+    },
+    FilterField {
+        label: "Slot Size",
+        field_name: "slots.rank",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Skill",
+        field_name: "skills.skillName",
+        kind: FilterKind::Text,
+    },
+    FilterField {
+        label: "Armor Set",
+        field_name: "armorSet.name",
+        kind: FilterKind::Text,
+    },
+];
+
+const WEAPON_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Weapon Type",
+        field_name: "type",
+        kind: FilterKind::Choice(WEAPON_TYPES),
+    },
+    FilterField {
+        label: "Element",
+        field_name: "elements.type",
+        kind: FilterKind::Choice(ELEMENTS),
+    },
+    FilterField {
+        label: "Rarity",
+        field_name: "rarity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Affinity",
+        field_name: "attributes.affinity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Slot Count",
+        field_name: "slots",
+        kind: FilterKind::Count,
+    },
+    FilterField {
+        label: "Slot Size",
+        field_name: "slots.rank",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Phial Type",
+        field_name: "attributes.phialType",
+        kind: FilterKind::Keyword(PHIAL_TYPES),
+    },
+    FilterField {
+        label: "Shelling Type",
+        field_name: "attributes.shellingType",
+        kind: FilterKind::Keyword(SHELLING_TYPES),
+    },
+    FilterField {
+        label: "Ammo",
+        field_name: "attributes.ammoCapacities",
+        kind: FilterKind::Present(AMMO_TYPES),
+    },
+];
+
+const ITEM_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Rarity",
+        field_name: "rarity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Value",
+        field_name: "value",
+        kind: FilterKind::Range,
+    },
+];
+
+const DECORATION_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Slot Size",
+        field_name: "slot",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Rarity",
+        field_name: "rarity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Skill",
+        field_name: "skills.skillName",
+        kind: FilterKind::Text,
+    },
+];
+
+const CHARM_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Rarity",
+        field_name: "ranks.rarity",
+        kind: FilterKind::Number,
+    },
+    FilterField {
+        label: "Skill",
+        field_name: "ranks.skills.skillName",
+        kind: FilterKind::Text,
+    },
+];
+
+const MONSTER_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Type",
+        field_name: "type",
+        kind: FilterKind::Choice(MONSTER_TYPES),
+    },
+    FilterField {
+        label: "Species",
+        field_name: "species",
+        kind: FilterKind::Text,
+    },
+    FilterField {
+        label: "Weakness",
+        field_name: "weaknesses.element",
+        kind: FilterKind::Choice(ELEMENTS),
+    },
+    FilterField {
+        label: "Location",
+        field_name: "locations.name",
+        kind: FilterKind::Text,
+    },
+];
+
+const ARMOR_SET_FIELDS: &[FilterField] = &[
+    FilterField {
+        label: "Rank",
+        field_name: "rank",
+        kind: FilterKind::Choice(ARMOR_RANKS),
+    },
+    FilterField {
+        label: "Bonus Skill",
+        field_name: "bonus.ranks.skill.skillName",
+        kind: FilterKind::Text,
+    },
+    FilterField {
+        label: "Piece Skill",
+        field_name: "pieces.skills.skillName",
+        kind: FilterKind::Text,
+    },
+];
+
+pub fn get_filter_fields(search_category: &SearchCategory) -> &'static [FilterField] {
     match search_category {
-        _ => {
-            let mut v = Vec::default();
-            v.push("Filter A".to_owned().into());
-            v
+        SearchCategory::Armor => ARMOR_FIELDS,
+        SearchCategory::Weapons => WEAPON_FIELDS,
+        SearchCategory::Items => ITEM_FIELDS,
+        SearchCategory::Decorations => DECORATION_FIELDS,
+        SearchCategory::Charms => CHARM_FIELDS,
+        SearchCategory::Monsters => MONSTER_FIELDS,
+        SearchCategory::ArmorSets => ARMOR_SET_FIELDS,
+        _ => &[],
+    }
+}
+
+pub fn get_filter_categories(search_category: &SearchCategory) -> Vec<ImString> {
+    get_filter_fields(search_category)
+        .iter()
+        .map(|field| ImString::new(field.label))
+        .collect()
+}
+
+//
+// Number Comparison
+//
+// the last two take a list of values instead, and only make sense for plain numbers
+const NUMBER_OPS: [&str; 8] = ["=", "!=", ">", ">=", "<", "<=", "in", "not in"];
+const LIST_OPS_START: i32 = 6;
+
+fn number_filter(op_idx: i32, value: QueryValue) -> QueryFilterType {
+    match op_idx {
+        1 => QueryFilterType::NotEqual(value),
+        2 => QueryFilterType::GreaterThan(value),
+        3 => QueryFilterType::GreaterThanOrEqual(value),
+        4 => QueryFilterType::LessThan(value),
+        5 => QueryFilterType::LessThanOrEqual(value),
+        _ => QueryFilterType::Exact(value),
+    }
+}
+
+// Reads "1, 2 3" style lists, skipping anything that isn't a number
+fn number_list_filter(op_idx: i32, text: &str) -> Option<QueryFilterType> {
+    let values = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|value| value.parse::<i32>().ok())
+        .map(QueryValue::from)
+        .collect::<Vec<_>>();
+    if values.is_empty() {
+        return None;
+    }
+    Some(match op_idx {
+        LIST_OPS_START => QueryFilterType::In(values),
+        _ => QueryFilterType::NotIn(values),
+    })
+}
+
+// There's no operator for the length of an array, so counts are checked by which of its
+// positions exist: "at least 2 slots" is `slots.1` existing, "at most 2" is `slots.2` missing.
+fn count_filter(field_name: &str, op_idx: i32, count: i32) -> QueryFilter {
+    // below the first position we check the array itself, which every entry has
+    let position = |idx: i32, exists: bool| {
+        let path = if idx < 0 {
+            field_name.to_owned()
+        } else {
+            format!("{}.{}", field_name, idx)
+        };
+        QueryFilter::new(path, QueryFilterType::Exists(exists))
+    };
+    let at_least = |count: i32| position(count.max(0) - 1, true);
+    let at_most = |count: i32| position(count.max(-1), false);
+
+    match op_idx {
+        1 => QueryFilter::any(vec![at_most(count - 1), at_least(count + 1)]),
+        2 => at_least(count + 1),
+        3 => at_least(count),
+        4 => at_most(count - 1),
+        5 => at_most(count),
+        _ => QueryFilter::all(vec![at_least(count), at_most(count)]),
+    }
+}
+
+//
+// Filter Row
+//
+#[derive(Debug)]
+pub struct FilterRow {
+    field_idx: i32,
+    op_idx: i32,
+    choice_idx: i32,
+    number: i32,
+    range: (i32, i32),
+    text: ImString,
+}
+
+impl Default for FilterRow {
+    fn default() -> Self {
+        Self {
+            field_idx: 0,
+            op_idx: 0,
+            choice_idx: 0,
+            number: 0,
+            range: (0, 0),
+            text: ImString::with_capacity(64),
+        }
+    }
+}
+
+impl FilterRow {
+    pub fn to_query_filter(&self, search_category: &SearchCategory) -> Option<QueryFilter> {
+        let field = get_filter_fields(search_category).get(self.field_idx as usize)?;
+        let field_name = field.field_name.to_owned();
+
+        match field.kind {
+            FilterKind::Choice(options) => {
+                let (_, value) = options.get(self.choice_idx as usize)?;
+                Some(QueryFilter::new(
+                    field_name,
+                    QueryFilterType::Exact((*value).into()),
+                ))
+            }
+            FilterKind::Keyword(options) => {
+                // matching anywhere in the value would find "power element" for "power"
+                let (_, value) = options.get(self.choice_idx as usize)?;
+                let filter = if value.contains('%') {
+                    QueryFilterType::Like((*value).to_owned())
+                } else {
+                    QueryFilterType::Exact((*value).into())
+                };
+                Some(QueryFilter::new(field_name, filter))
+            }
+            FilterKind::Present(options) => {
+                let (_, suffix) = options.get(self.choice_idx as usize)?;
+                Some(QueryFilter::new(
+                    format!("{}.{}", field_name, suffix),
+                    QueryFilterType::Exists(true),
+                ))
+            }
+            FilterKind::Number if self.op_idx >= LIST_OPS_START => Some(QueryFilter::new(
+                field_name,
+                number_list_filter(self.op_idx, self.text.to_str())?,
+            )),
+            FilterKind::Number => Some(QueryFilter::new(
+                field_name,
+                number_filter(self.op_idx, self.number.into()),
+            )),
+            FilterKind::Count => Some(count_filter(field.field_name, self.op_idx, self.number)),
+            FilterKind::Range => Some(QueryFilter::new(
+                field_name,
+                QueryFilterType::Between(self.range.0.into(), self.range.1.into()),
+            )),
+            FilterKind::Text => {
+                let text = self.text.to_str().trim();
+                if text.is_empty() {
+                    return None;
+                }
+                Some(QueryFilter::new(
+                    field_name,
+                    QueryFilterType::Like(format!("%{}%", text.replace("*", "%"))),
+                ))
+            }
         }
     }
+
+    // Draws the editing widgets for this row; returns true if the row should be removed
+    pub fn layout<'a>(&mut self, ui: &Ui<'a>, search_category: &SearchCategory) -> bool {
+        let fields = get_filter_fields(search_category);
+        let field_names = get_filter_categories(search_category);
+        let ref_names = field_names
+            .iter()
+            .map(std::convert::AsRef::as_ref)
+            .collect::<Vec<_>>();
+
+        ui.with_item_width(160.0, || {
+            if ui.combo(
+                im_str!("##filter_field"),
+                &mut self.field_idx,
+                ref_names.as_slice(),
+                ref_names.len() as i32,
+            ) {
+                // options and values differ between fields, so start the new one from scratch
+                *self = Self {
+                    field_idx: self.field_idx,
+                    ..Default::default()
+                };
+            }
+        });
+
+        let field = match fields.get(self.field_idx as usize) {
+            Some(field) => field,
+            None => return true,
+        };
+
+        ui.same_line(0.0);
+        match field.kind {
+            FilterKind::Choice(options)
+            | FilterKind::Keyword(options)
+            | FilterKind::Present(options) => {
+                let labels = options
+                    .iter()
+                    .map(|(label, _)| ImString::new(*label))
+                    .collect::<Vec<_>>();
+                let ref_labels = labels
+                    .iter()
+                    .map(std::convert::AsRef::as_ref)
+                    .collect::<Vec<_>>();
+                ui.with_item_width(160.0, || {
+                    ui.combo(
+                        im_str!("##filter_choice"),
+                        &mut self.choice_idx,
+                        ref_labels.as_slice(),
+                        ref_labels.len() as i32,
+                    );
+                });
+            }
+            FilterKind::Number | FilterKind::Count => {
+                let op_count = match field.kind {
+                    FilterKind::Count => LIST_OPS_START as usize,
+                    _ => NUMBER_OPS.len(),
+                };
+                let ops = NUMBER_OPS[..op_count]
+                    .iter()
+                    .map(|op| ImString::new(*op))
+                    .collect::<Vec<_>>();
+                let ref_ops = ops
+                    .iter()
+                    .map(std::convert::AsRef::as_ref)
+                    .collect::<Vec<_>>();
+                ui.with_item_width(50.0, || {
+                    ui.combo(
+                        im_str!("##filter_op"),
+                        &mut self.op_idx,
+                        ref_ops.as_slice(),
+                        ref_ops.len() as i32,
+                    );
+                });
+                ui.same_line(0.0);
+                ui.with_item_width(100.0, || {
+                    if self.op_idx >= LIST_OPS_START {
+                        ui.input_text(im_str!("##filter_list"), &mut self.text)
+                            .build();
+                    } else {
+                        ui.input_int(im_str!("##filter_number"), &mut self.number)
+                            .build();
+                    }
+                });
+            }
+            FilterKind::Range => {
+                ui.with_item_width(100.0, || {
+                    ui.input_int(im_str!("##filter_min"), &mut self.range.0)
+                        .build();
+                });
+                ui.same_line(0.0);
+                ui.text("to");
+                ui.same_line(0.0);
+                ui.with_item_width(100.0, || {
+                    ui.input_int(im_str!("##filter_max"), &mut self.range.1)
+                        .build();
+                });
+            }
+            FilterKind::Text => {
+                ui.with_item_width(160.0, || {
+                    ui.input_text(im_str!("##filter_text"), &mut self.text)
+                        .build();
+                });
+            }
+        }
+
+        ui.same_line(0.0);
+        ui.small_button(im_str!("Remove"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::database::LocalDatabase;
+    use super::super::query::QueryInfo;
+    use super::*;
+    use std::path::Path;
+
+    // Ids of the armor pieces with `count` slots, compared with `op`
+    fn armor_with_slots(op: &str, count: i32) -> Vec<i64> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/db");
+        let op_idx = NUMBER_OPS.iter().position(|&name| name == op).unwrap();
+        let query = QueryInfo::find_category(SearchCategory::Armor).with_filter(count_filter(
+            "slots",
+            op_idx as i32,
+            count,
+        ));
+        let found = LocalDatabase::load(&dir).unwrap().query(&query).unwrap();
+        found
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn slot_counts_compare_by_position() {
+        assert_eq!(armor_with_slots("=", 0), vec![1, 14]);
+        assert_eq!(armor_with_slots("=", 2), vec![15, 17]);
+        assert_eq!(armor_with_slots("!=", 1), vec![1, 14, 15, 17]);
+        assert_eq!(armor_with_slots(">", 1), vec![15, 17]);
+        assert_eq!(armor_with_slots(">=", 1), vec![10, 11, 12, 13, 15, 16, 17]);
+        assert_eq!(armor_with_slots("<", 1), vec![1, 14]);
+        assert_eq!(armor_with_slots("<=", 1), vec![1, 10, 11, 12, 13, 14, 16]);
+    }
+
+    #[test]
+    fn keywords_only_match_their_own_type() {
+        let phial = |label: &str| {
+            let row = FilterRow {
+                field_idx: WEAPON_FIELDS
+                    .iter()
+                    .position(|field| field.label == "Phial Type")
+                    .unwrap() as i32,
+                choice_idx: PHIAL_TYPES
+                    .iter()
+                    .position(|(name, _)| *name == label)
+                    .unwrap() as i32,
+                ..Default::default()
+            };
+            row.to_query_filter(&SearchCategory::Weapons)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(phial("Power"), "{\"attributes.phialType\":%22power%22}");
+        assert_eq!(
+            phial("Dragon"),
+            "{\"attributes.phialType\":{\"$like\":%22dragon%20%25%22}}"
+        );
+    }
+
+    #[test]
+    fn number_lists_become_set_filters() {
+        let list = |op_idx: i32, text: &str| {
+            number_list_filter(op_idx, text).map(|filter| filter.to_string())
+        };
+        assert_eq!(
+            list(LIST_OPS_START, "1, 2 x 3").as_deref(),
+            Some("{\"$in\":[1,2,3]}")
+        );
+        assert_eq!(
+            list(LIST_OPS_START + 1, "4").as_deref(),
+            Some("{\"$nin\":[4]}")
+        );
+        assert_eq!(list(LIST_OPS_START, " , "), None);
+    }
+
+    #[test]
+    fn counts_at_zero_and_below() {
+        assert_eq!(armor_with_slots(">=", 0).len(), 9);
+        assert_eq!(armor_with_slots("<", 0), Vec::<i64>::new());
+        assert_eq!(armor_with_slots("=", -1), Vec::<i64>::new());
+    }
 }
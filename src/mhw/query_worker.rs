@@ -0,0 +1,114 @@
+use super::common::MhwEvent;
+use super::query::MHWQueryError;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const WORKER_THREAD_COUNT: usize = 4;
+
+// A job returns the event to deliver once it finishes, or None if it was cancelled
+type Job = Box<dyn FnOnce() -> Option<MhwEvent> + Send>;
+
+//
+// Request Tracker
+//
+// Hands out increasing tickets so that only the newest request of a kind is acted on. Jobs check
+// their ticket before starting so stale requests still waiting in the queue are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct RequestTracker {
+    latest: Arc<AtomicUsize>,
+}
+
+impl RequestTracker {
+    pub fn next(&self) -> usize {
+        self.latest.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn is_current(&self, ticket: usize) -> bool {
+        self.latest.load(Ordering::SeqCst) == ticket
+    }
+}
+
+//
+// Query Worker
+//
+pub struct QueryWorker {
+    jobs: Sender<Job>,
+    results: Receiver<MhwEvent>,
+    problems: Sender<MhwEvent>, // for reporting the worker's own failures
+}
+
+impl QueryWorker {
+    pub fn new() -> Self {
+        let (job_sender, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for idx in 0..WORKER_THREAD_COUNT {
+            let jobs = Arc::clone(&job_receiver);
+            let results = result_sender.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("query-worker-{}", idx))
+                .spawn(move || loop {
+                    // only hold the lock long enough to take the next job
+                    let job = match jobs.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    let job = match job {
+                        Ok(job) => job,
+                        Err(_) => return, // the worker was dropped
+                    };
+
+                    if let Some(evt) = job() {
+                        if results.send(evt).is_err() {
+                            return;
+                        }
+                    }
+                });
+
+            if let Err(e) = spawned {
+                let error = MHWQueryError::Internal(format!("Unable to start thread: {}", e));
+                let _ = result_sender
+                    .send(MhwEvent::ShowError("Starting a query worker failed", error));
+            }
+        }
+
+        Self {
+            jobs: job_sender,
+            results: result_receiver,
+            problems: result_sender,
+        }
+    }
+
+    pub fn spawn<F>(&self, job: F)
+    where
+        F: FnOnce() -> Option<MhwEvent> + Send + 'static,
+    {
+        if self.jobs.send(Box::new(job)).is_err() {
+            let error = MHWQueryError::Internal("The query worker is no longer running".to_owned());
+            let _ = self
+                .problems
+                .send(MhwEvent::ShowError("Running a query failed", error));
+        }
+    }
+
+    // Collects the events from every job that has finished since the last poll
+    pub fn poll(&self) -> Vec<MhwEvent> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl Default for QueryWorker {
+    fn default() -> Self {
+        QueryWorker::new()
+    }
+}
+
+impl fmt::Debug for QueryWorker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "QueryWorker({} threads)", WORKER_THREAD_COUNT)
+    }
+}
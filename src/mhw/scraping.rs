@@ -1,22 +1,317 @@
-use scraper::{Html, Selector};
+use super::query::MHWQueryError;
 use reqwest;
+use scraper::{ElementRef, Html, Selector};
 
-pub fn load_page(url: &str) -> Result<String, reqwest::Error>
-{
-    let mut result = reqwest::get(url)?;
+// Pulls the monster details mhw-db doesn't have (hitzones, part thresholds and drop rates) out of
+// a kiranico monster page, e.g. https://mhworld.kiranico.com/monster/anjanath
 
-    return result.text()
+const KIRANICO_MONSTER_URL: &str = "https://mhworld.kiranico.com/monster";
+
+// Kiranico names its pages after the monster, e.g. "Kulu-Ya-Ku" -> ".../monster/kulu-ya-ku"
+pub fn monster_page_url(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("{}/{}", KIRANICO_MONSTER_URL, slug)
+}
+
+pub fn load_page(url: &str) -> Result<String, reqwest::Error> {
+    let mut result = reqwest::get(url)?.error_for_status()?;
+
+    result.text()
+}
+
+// Fetches and parses the kiranico page for a monster
+pub fn load_monster(name: &str) -> Result<ScrapedMonster, MHWQueryError> {
+    let url = monster_page_url(name);
+    let html = load_page(url.as_str())?;
+    match scrape_monster(html.as_str()) {
+        Ok(monster) => Ok(monster),
+        Err(message) => Err(MHWQueryError::Decode { path: url, message }),
+    }
+}
+
+//
+// Hitzone
+//
+// Each value is how much of that damage type the part takes, in percent
+#[derive(Debug, Default, PartialEq)]
+pub struct Hitzone {
+    pub part: String,
+    pub sever: i32,
+    pub blunt: i32,
+    pub shot: i32,
+    pub fire: i32,
+    pub water: i32,
+    pub thunder: i32,
+    pub ice: i32,
+    pub dragon: i32,
+    pub stun: i32,
+}
+
+//
+// Part Thresholds
+//
+// Damage needed to flinch, break or sever a part; None if the part can't be broken/severed
+#[derive(Debug, Default, PartialEq)]
+pub struct PartThresholds {
+    pub part: String,
+    pub stagger: Option<i32>,
+    pub break_value: Option<i32>,
+    pub sever: Option<i32>,
+}
+
+//
+// Reward Drop
+//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewardRank {
+    Low,
+    High,
+    Master,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RewardDrop {
+    pub rank: RewardRank,
+    pub condition: String, // carve, target rewards, broken part, etc.
+    pub item: String,
+    pub quantity: i32,
+    pub chance: i32, // percent
+}
+
+//
+// Scraped Monster
+//
+#[derive(Debug, Default, PartialEq)]
+pub struct ScrapedMonster {
+    pub name: String,
+    pub hitzones: Vec<Hitzone>,
+    pub thresholds: Vec<PartThresholds>,
+    pub rewards: Vec<RewardDrop>,
+}
+
+fn selector(text: &str) -> Result<Selector, String> {
+    Selector::parse(text).map_err(|e| format!("Invalid selector {}: {:?}", text, e))
+}
+
+fn element_text(elem: &ElementRef) -> String {
+    elem.text().collect::<String>().trim().to_owned()
+}
+
+// Reads numbers like "45", "x2" or "38%"; "-" and blanks are None
+fn parse_number(text: &str) -> Option<i32> {
+    text.trim()
+        .trim_start_matches('x')
+        .trim_end_matches('%')
+        .trim()
+        .parse::<i32>()
+        .ok()
+}
+
+fn reward_rank(title: &str) -> Option<RewardRank> {
+    let title = title.to_lowercase();
+    if title.contains("low rank") {
+        Some(RewardRank::Low)
+    } else if title.contains("high rank") {
+        Some(RewardRank::High)
+    } else if title.contains("master rank") {
+        Some(RewardRank::Master)
+    } else {
+        None
+    }
 }
 
-pub fn scrape_monster(html_text: &String) -> Result<(), String>
-{
-    let document = Html::parse_fragment(html_text);
-    let header_selector = Selector::parse("h1,h4").unwrap();
-    let mut found = document.select(&header_selector);
-    
-    while let Some(elem) = found.next() {
-        println!("{:?}", elem.inner_html());
+fn parse_hitzone(headers: &[String], cells: &[String]) -> Option<Hitzone> {
+    let mut hitzone = Hitzone {
+        part: cells.first()?.clone(),
+        ..Default::default()
+    };
+
+    for (header, cell) in headers.iter().zip(cells.iter()).skip(1) {
+        let value = parse_number(cell).unwrap_or(0);
+        match header.as_str() {
+            "sever" | "cut" => hitzone.sever = value,
+            "blunt" | "impact" => hitzone.blunt = value,
+            "shot" => hitzone.shot = value,
+            "fire" => hitzone.fire = value,
+            "water" => hitzone.water = value,
+            "thunder" => hitzone.thunder = value,
+            "ice" => hitzone.ice = value,
+            "dragon" => hitzone.dragon = value,
+            "stun" | "ko" => hitzone.stun = value,
+            _ => {}
+        }
+    }
+    Some(hitzone)
+}
+
+fn parse_thresholds(headers: &[String], cells: &[String]) -> Option<PartThresholds> {
+    let mut thresholds = PartThresholds {
+        part: cells.first()?.clone(),
+        ..Default::default()
+    };
+
+    for (header, cell) in headers.iter().zip(cells.iter()).skip(1) {
+        let value = parse_number(cell);
+        match header.as_str() {
+            "stagger" | "flinch" => thresholds.stagger = value,
+            "break" => thresholds.break_value = value,
+            "sever" => thresholds.sever = value,
+            _ => {}
+        }
+    }
+    Some(thresholds)
+}
+
+pub fn scrape_monster(html_text: &str) -> Result<ScrapedMonster, String> {
+    let document = Html::parse_document(html_text);
+    let name_selector = selector("h1")?;
+    let card_selector = selector("div.card")?;
+    let card_title_selector = selector(".card-header")?;
+    let table_selector = selector("table")?;
+    let header_selector = selector("thead th")?;
+    let row_selector = selector("tbody tr")?;
+    let cell_selector = selector("td")?;
+
+    let mut monster = ScrapedMonster {
+        name: match document.select(&name_selector).next() {
+            Some(elem) => element_text(&elem),
+            None => return Err("No monster name found".to_owned()),
+        },
+        ..Default::default()
+    };
+
+    for card in document.select(&card_selector) {
+        let title = card
+            .select(&card_title_selector)
+            .next()
+            .map(|elem| element_text(&elem))
+            .unwrap_or_default();
+        let rank = reward_rank(title.as_str());
+
+        for table in card.select(&table_selector) {
+            let headers = table
+                .select(&header_selector)
+                .map(|elem| element_text(&elem).to_lowercase())
+                .collect::<Vec<_>>();
+            let rows = table.select(&row_selector).map(|row| {
+                row.select(&cell_selector)
+                    .map(|elem| element_text(&elem))
+                    .collect::<Vec<_>>()
+            });
+
+            // tell the tables apart by their columns, since their order on the page can change
+            let has_column = |name: &str| headers.iter().any(|h| h == name);
+            if let Some(rank) = rank {
+                // conditions span several rows, so short rows reuse the last one seen
+                let mut condition = String::new();
+                for cells in rows {
+                    let item_cells = match cells.len() {
+                        4 => {
+                            condition = cells[0].clone();
+                            &cells[1..]
+                        }
+                        3 => &cells[..],
+                        _ => continue,
+                    };
+                    monster.rewards.push(RewardDrop {
+                        rank,
+                        condition: condition.clone(),
+                        item: item_cells[0].clone(),
+                        quantity: parse_number(&item_cells[1]).unwrap_or(1),
+                        chance: parse_number(&item_cells[2]).unwrap_or(0),
+                    });
+                }
+            } else if has_column("blunt") || has_column("impact") {
+                monster
+                    .hitzones
+                    .extend(rows.filter_map(|cells| parse_hitzone(&headers, &cells)));
+            } else if has_column("stagger") || has_column("break") {
+                monster
+                    .thresholds
+                    .extend(rows.filter_map(|cells| parse_thresholds(&headers, &cells)));
+            }
+        }
+    }
+
+    Ok(monster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANJANATH_HTML: &str = include_str!("../../fixtures/kiranico/anjanath.html");
+
+    #[test]
+    fn page_urls_follow_monster_names() {
+        assert_eq!(
+            monster_page_url("Kulu-Ya-Ku"),
+            "https://mhworld.kiranico.com/monster/kulu-ya-ku"
+        );
+        assert_eq!(
+            monster_page_url("Great Jagras"),
+            "https://mhworld.kiranico.com/monster/great-jagras"
+        );
+    }
+
+    #[test]
+    fn scrapes_hitzones() {
+        let monster = scrape_monster(ANJANATH_HTML).unwrap();
+        assert_eq!(monster.name, "Anjanath");
+        assert_eq!(monster.hitzones.len(), 4);
+        assert_eq!(
+            monster.hitzones[1],
+            Hitzone {
+                part: "Nose (Inflated)".to_owned(),
+                sever: 75,
+                blunt: 80,
+                shot: 70,
+                fire: 0,
+                water: 30,
+                thunder: 10,
+                ice: 25,
+                dragon: 15,
+                stun: 100,
+            }
+        );
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn scrapes_part_thresholds() {
+        let monster = scrape_monster(ANJANATH_HTML).unwrap();
+        assert_eq!(monster.thresholds.len(), 3);
+        assert_eq!(monster.thresholds[0].break_value, Some(300));
+        assert_eq!(monster.thresholds[1].break_value, None);
+        assert_eq!(monster.thresholds[2].sever, Some(240));
+    }
+
+    #[test]
+    fn scrapes_rewards_by_rank() {
+        let monster = scrape_monster(ANJANATH_HTML).unwrap();
+        let low_rank = monster
+            .rewards
+            .iter()
+            .filter(|drop| drop.rank == RewardRank::Low)
+            .collect::<Vec<_>>();
+        assert_eq!(low_rank.len(), 4);
+
+        // the fang row shares the carve condition with the row above it
+        assert_eq!(low_rank[1].condition, "Carve");
+        assert_eq!(low_rank[1].item, "Anjanath Fang");
+        assert_eq!(low_rank[1].chance, 20);
+        assert_eq!(low_rank[2].quantity, 2);
+
+        let gem = monster
+            .rewards
+            .iter()
+            .find(|drop| drop.item == "Anjanath Gem");
+        assert_eq!(
+            gem.map(|drop| (drop.rank, drop.chance)),
+            Some((RewardRank::High, 3))
+        );
+    }
+}
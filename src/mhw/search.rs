@@ -1,10 +1,7 @@
-use super::armor::ArmorInfo;
 use super::common::{GuiDetails, MhwEvent, MhwGui};
-use super::entry_display::EntryDisplayState;
-use super::items::ItemInfo;
+use super::entry_display::EntryRequest;
 use super::query::*;
-use super::query_filters;
-use super::weapons::WeaponInfo;
+use super::query_filters::FilterRow;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use std::fmt;
@@ -18,18 +15,18 @@ const SEARCH_WINDOW_WIDTH: f32 = 240f32;
 //
 // Search Category
 //
-#[derive(Debug, Clone, Copy, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive, Default)]
 pub enum SearchCategory {
+    #[default]
     Armor = 0,
     Weapons,
     Items,
-    MAX,
-}
-
-impl Default for SearchCategory {
-    fn default() -> Self {
-        SearchCategory::Armor
-    }
+    Skills,
+    Decorations,
+    Charms,
+    Monsters,
+    ArmorSets,
+    Count, // how many categories there are, not one itself
 }
 
 impl fmt::Display for SearchCategory {
@@ -38,6 +35,11 @@ impl fmt::Display for SearchCategory {
             SearchCategory::Armor => write!(f, "armor"),
             SearchCategory::Weapons => write!(f, "weapons"),
             SearchCategory::Items => write!(f, "items"),
+            SearchCategory::Skills => write!(f, "skills"),
+            SearchCategory::Decorations => write!(f, "decorations"),
+            SearchCategory::Charms => write!(f, "charms"),
+            SearchCategory::Monsters => write!(f, "monsters"),
+            SearchCategory::ArmorSets => write!(f, "armor/sets"),
             _ => write!(f, "ERROR"),
         }
     }
@@ -56,8 +58,9 @@ pub struct SearchState {
     pub selected_item: i32,
     pub should_draw: bool,
     pub results: Vec<SearchResults>,
-    // TODO: add filters
-    filters: Vec<QueryFilter>,
+    pub searching: bool,
+    filter_rows: Vec<FilterRow>,
+    filter_combinator: QueryCombinator, // whether every filter row has to match or just one
 }
 
 impl Default for SearchState {
@@ -68,89 +71,47 @@ impl Default for SearchState {
             selected_item: -1,
             should_draw: true,
             results: vec![],
-            filters: vec![],
+            searching: false,
+            filter_rows: vec![],
+            filter_combinator: Default::default(),
         }
     }
 }
 
 impl SearchState {
-    pub fn query_api(&mut self) {
+    fn active_filters(&self) -> Vec<QueryFilter> {
+        self.filter_rows
+            .iter()
+            .filter_map(|row| row.to_query_filter(&self.search_type))
+            .collect()
+    }
+
+    pub fn query_api(&mut self, event_queue: &mut VecDeque<MhwEvent>) {
         self.results.clear();
         self.selected_item = -1;
+        self.searching = true;
 
-        let found = QueryInfo::find_ids(self.text.to_str(), self.search_type).execute_mhw_query();
-        self.results = match found {
-            Ok(v) => v,
-            Err(e) => {
-                println!("API Query failed: {}", e);
-                return;
-            }
-        };
-
-        println!("Found: {}", self.results.len());
+        // the name always has to match, however the filter rows are combined
+        let mut query = QueryInfo::find_ids(self.text.to_str(), self.search_type);
+        let filters = self.active_filters();
+        if !filters.is_empty() {
+            query = query.with_filter(self.filter_combinator.combine(filters));
+        }
+        event_queue.push_back(MhwEvent::RequestSearch(query));
     }
 
-    fn get_entry_for_selection(&self) -> EntryDisplayState {
-        let id_string = if self.selected_item >= 0 {
-            let idx = self.selected_item as usize;
-            format!("{}", self.results[idx].id)
-        } else {
-            return EntryDisplayState::None;
-        };
-        let filter = QueryFilter::new("id".to_owned(), QueryFilterType::Exact(id_string));
-        let query = QueryInfo::find_category(self.search_type).with_filter(filter);
+    pub fn set_results(&mut self, results: Vec<SearchResults>) {
+        self.searching = false;
+        self.results = results;
+    }
 
-        match self.search_type {
-            SearchCategory::Armor => {
-                let found: Result<Vec<ArmorInfo>, MHWQueryError> = query.execute_mhw_query();
-                match found {
-                    Ok(mut f) => {
-                        if !f.is_empty() {
-                            EntryDisplayState::Armor(f.remove(0))
-                        } else {
-                            EntryDisplayState::None
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error retrieving entry: {}", e);
-                        EntryDisplayState::None
-                    }
-                }
-            }
-            SearchCategory::Weapons => {
-                let found: Result<Vec<WeaponInfo>, MHWQueryError> = query.execute_mhw_query();
-                match found {
-                    Ok(mut f) => {
-                        if !f.is_empty() {
-                            EntryDisplayState::Weapon(f.remove(0))
-                        } else {
-                            EntryDisplayState::None
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error retrieving entry: {}", e);
-                        EntryDisplayState::None
-                    }
-                }
-            }
-            SearchCategory::Items => {
-                let found: Result<Vec<ItemInfo>, MHWQueryError> = query.execute_mhw_query();
-                match found {
-                    Ok(mut f) => {
-                        if !f.is_empty() {
-                            EntryDisplayState::Item(f.remove(0))
-                        } else {
-                            EntryDisplayState::None
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error retrieving entry: {}", e);
-                        EntryDisplayState::None
-                    }
-                }
-            }
-            _ => EntryDisplayState::None,
+    fn get_request_for_selection(&self) -> Option<EntryRequest> {
+        if self.selected_item < 0 {
+            return None;
         }
+
+        let found = self.results.get(self.selected_item as usize)?;
+        Some(EntryRequest::new(self.search_type, found.id as i32))
     }
 }
 
@@ -173,21 +134,44 @@ impl SearchState {
             .size(window_size, ImGuiCond::Always)
             .flags(ImGuiWindowFlags::NoDecoration);
         window.build(|| {
-            let categories = query_filters::get_filter_categories(&self.search_type);
-            let ref_names = categories
-                .iter()
-                .map(std::convert::AsRef::as_ref)
-                .collect::<Vec<_>>();
-            //for mut filter in &self.filters {
-            // draw the stuff
-            let mut idx = 0;
-            if ui.combo(
-                im_str!("##filter_combo"),
-                &mut idx,
-                ref_names.as_slice(),
-                SearchCategory::MAX as i32,
-            ) {}
-            //}
+            let mut combinator_idx = match self.filter_combinator {
+                QueryCombinator::All => 0,
+                QueryCombinator::Any => 1,
+            };
+            ui.with_item_width(160.0, || {
+                if ui.combo(
+                    im_str!("##filter_combinator"),
+                    &mut combinator_idx,
+                    &[im_str!("Match all filters"), im_str!("Match any filter")],
+                    2,
+                ) {
+                    self.filter_combinator = match combinator_idx {
+                        1 => QueryCombinator::Any,
+                        _ => QueryCombinator::All,
+                    };
+                }
+            });
+
+            let search_type = self.search_type;
+            let mut removed = None;
+            for (idx, row) in self.filter_rows.iter_mut().enumerate() {
+                ui.with_id(idx as i32, || {
+                    if row.layout(ui, &search_type) {
+                        removed = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = removed {
+                self.filter_rows.remove(idx);
+            }
+
+            if ui.button(im_str!("Add Filter"), (0.0, 0.0)) {
+                self.filter_rows.push(Default::default());
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Clear"), (0.0, 0.0)) {
+                self.filter_rows.clear();
+            }
         });
     }
 }
@@ -219,12 +203,21 @@ impl MhwGui for SearchState {
         let mut build_func = || {
             // select category
             let mut idx = self.search_type as i32;
-            if ui.combo(
+            ui.combo(
                 im_str!("##category_combo"),
                 &mut idx,
-                &[im_str!("Armor"), im_str!("Weapons"), im_str!("Items")],
-                SearchCategory::MAX as i32,
-            ) {}
+                &[
+                    im_str!("Armor"),
+                    im_str!("Weapons"),
+                    im_str!("Items"),
+                    im_str!("Skills"),
+                    im_str!("Decorations"),
+                    im_str!("Charms"),
+                    im_str!("Monsters"),
+                    im_str!("Armor Sets"),
+                ],
+                SearchCategory::Count as i32,
+            );
             ui.same_line(0.0);
             ui.with_item_width(-1.0, || {
                 if ui.button(im_str!("Filter..."), (-1.0, 0.0)) {
@@ -232,6 +225,10 @@ impl MhwGui for SearchState {
                 }
             });
             if let Some(result) = SearchCategory::from_i32(idx) {
+                if result != self.search_type {
+                    // filter fields are per-category, so they don't carry over
+                    self.filter_rows.clear();
+                }
                 self.search_type = result;
             }
 
@@ -245,10 +242,13 @@ impl MhwGui for SearchState {
                     .build()
                 {
                     ui.set_keyboard_focus_here(-1);
-                    self.query_api();
+                    self.query_api(event_queue);
                 }
             });
             ui.separator();
+            if self.searching {
+                ui.text("Searching...");
+            }
             let results_list = &self.results;
             let names_list_imstring = results_list
                 .iter()
@@ -270,8 +270,9 @@ impl MhwGui for SearchState {
                 );
 
                 if did_change {
-                    let found = self.get_entry_for_selection();
-                    event_queue.push_back(MhwEvent::ShowState(found));
+                    if let Some(request) = self.get_request_for_selection() {
+                        event_queue.push_back(MhwEvent::RequestEntry(request));
+                    }
                 }
             });
         };
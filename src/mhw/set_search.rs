@@ -0,0 +1,1019 @@
+use super::armor::{ArmorInfo, ArmorRank};
+use super::armor_sets::ArmorSetInfo;
+use super::charms::CharmInfo;
+use super::common::{fonts::*, GuiDetails, MhwEvent, MhwGui, SkillRank};
+use super::data_source::DataSource;
+use super::decorations::DecorationInfo;
+use super::entry_display::EntryRequest;
+use super::loadout::ARMOR_TYPES;
+use super::query::{MHWQueryError, QueryInfo};
+use super::search::SearchCategory;
+use imgui::*;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, VecDeque};
+
+// Only the best few are worth showing
+const MAX_FOUND_SETS: usize = 50;
+
+// Stops runaway searches (e.g. only one easy skill picked); the results say when it kicks in
+const MAX_CHECKED_SETS: usize = 2_000_000;
+
+//
+// Set Search Request
+//
+#[derive(Debug, Clone)]
+pub struct SkillTarget {
+    pub skill: i32,
+    pub name: String,
+    pub level: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetSearchOrder {
+    Defense,
+    FreeSlots,
+    ExtraSkills,
+}
+
+#[derive(Debug)]
+pub struct SetSearchRequest {
+    pub targets: Vec<SkillTarget>,
+    pub rank: Option<ArmorRank>,
+    pub weapon_slots: Vec<i32>, // filled in from the loadout's weapon, if there is one
+    pub order: SetSearchOrder,
+}
+
+//
+// Found Set
+//
+#[derive(Debug, Clone)]
+pub struct FoundPiece {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoundSet {
+    pub armor: Vec<FoundPiece>,
+    pub charm: Option<FoundPiece>,
+    pub decorations: Vec<FoundPiece>,
+    pub defense: i32,
+    pub free_slots: Vec<i32>,
+    pub extra_skills: Vec<(String, i32)>,
+}
+
+impl FoundSet {
+    fn free_slot_total(&self) -> i32 {
+        self.free_slots.iter().sum()
+    }
+
+    fn extra_skill_total(&self) -> i32 {
+        self.extra_skills.iter().map(|(_, level)| level).sum()
+    }
+
+    // Ordering::Less means `self` should be listed first
+    fn compare(&self, other: &FoundSet, order: SetSearchOrder) -> Ordering {
+        let key = |set: &FoundSet| match order {
+            SetSearchOrder::Defense => {
+                (set.defense, set.free_slot_total(), set.extra_skill_total())
+            }
+            SetSearchOrder::FreeSlots => {
+                (set.free_slot_total(), set.defense, set.extra_skill_total())
+            }
+            SetSearchOrder::ExtraSkills => {
+                (set.extra_skill_total(), set.defense, set.free_slot_total())
+            }
+        };
+        key(other).cmp(&key(self))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SetSearchResults {
+    pub sets: Vec<FoundSet>,
+    pub complete: bool, // false if the search gave up before trying everything
+}
+
+//
+// Solver
+//
+// An armor piece boiled down to what the solver compares; `points` lines up with the targets
+#[derive(Debug)]
+struct Candidate {
+    piece: FoundPiece,
+    defense: i32,
+    points: Vec<i32>,
+    potential: Vec<i32>, // points plus whatever its slots could hold
+    slots: Vec<i32>,     // largest first
+    set: Option<i32>,
+    bonus_set: Option<i32>, // the set, only if its bonus grants one of the targets
+    extra: Vec<(i32, String, i32)>,
+}
+
+impl Candidate {
+    fn extra_total(&self) -> i32 {
+        self.extra.iter().map(|(_, _, level)| level).sum()
+    }
+
+    fn dominates(&self, other: &Candidate, order: SetSearchOrder) -> bool {
+        if other.bonus_set.is_some() && other.bonus_set != self.bonus_set {
+            return false;
+        }
+        // extra skills only count when the sets are ranked by them
+        if order == SetSearchOrder::ExtraSkills && self.extra_total() < other.extra_total() {
+            return false;
+        }
+        self.defense >= other.defense
+            && self.slots.len() >= other.slots.len()
+            && self.slots.iter().zip(&other.slots).all(|(a, b)| a >= b)
+            && self.points.iter().zip(&other.points).all(|(a, b)| a >= b)
+    }
+}
+
+// The decoration used to make up each target's missing points
+#[derive(Debug)]
+struct Jewel {
+    index: usize, // into SetSearchData::decorations
+    size: i32,
+    points: i32,
+}
+
+// How many points of a target a set of slots could hold at most
+fn slot_potential(slots: &[i32], jewel: Option<&Jewel>) -> i32 {
+    match jewel {
+        Some(jewel) => {
+            slots.iter().filter(|size| **size >= jewel.size).count() as i32 * jewel.points
+        }
+        None => 0,
+    }
+}
+
+fn target_index(targets: &[SkillTarget], skill: i32) -> Option<usize> {
+    targets.iter().position(|target| target.skill == skill)
+}
+
+// Keeps only the pieces no other piece beats on every count that matters
+fn prune_dominated(mut candidates: Vec<Candidate>, order: SetSearchOrder) -> Vec<Candidate> {
+    candidates.sort_by_key(|candidate| Reverse(candidate.defense));
+    let mut kept: Vec<Candidate> = vec![];
+    for candidate in candidates {
+        if kept.iter().any(|other| other.dominates(&candidate, order)) {
+            continue;
+        }
+        kept.retain(|other| !candidate.dominates(other, order));
+        kept.push(candidate);
+    }
+    kept
+}
+
+// Skill levels of a set being checked, split into the targets and everything else
+struct SkillTally<'a> {
+    targets: &'a [SkillTarget],
+    points: Vec<i32>,
+    extra: BTreeMap<i32, (String, i32)>,
+}
+
+impl<'a> SkillTally<'a> {
+    fn new(targets: &'a [SkillTarget]) -> Self {
+        Self {
+            targets,
+            points: vec![0; targets.len()],
+            extra: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, skill: i32, name: &str, level: i32) {
+        match target_index(self.targets, skill) {
+            Some(idx) => self.points[idx] += level,
+            None => {
+                self.extra
+                    .entry(skill)
+                    .or_insert_with(|| (name.to_owned(), 0))
+                    .1 += level
+            }
+        }
+    }
+}
+
+// Everything the solver works from, loaded from the current data source
+#[derive(Debug)]
+pub struct SetSearchData {
+    pub armor: Vec<ArmorInfo>,
+    pub charms: Vec<CharmInfo>,
+    pub decorations: Vec<DecorationInfo>,
+    pub armor_sets: Vec<ArmorSetInfo>,
+}
+
+struct Search<'a> {
+    request: &'a SetSearchRequest,
+    data: &'a SetSearchData,
+    jewels: Vec<Option<Jewel>>,
+    pieces: Vec<Vec<Candidate>>, // one list per armor type
+    charms: Vec<(FoundPiece, &'a [SkillRank])>,
+    charm_bound: Vec<i32>,
+    fixed_bound: Vec<i32>,          // weapon slots and set bonuses
+    remaining_bound: Vec<Vec<i32>>, // best potential of the armor types not picked yet
+    remaining_defense: Vec<i32>,
+    chosen: Vec<usize>,
+    found: Vec<FoundSet>,
+    checked: usize,
+}
+
+impl SetSearchData {
+    pub fn load(source: &dyn DataSource) -> Result<Self, MHWQueryError> {
+        Ok(Self {
+            armor: QueryInfo::find_category(SearchCategory::Armor).execute(source)?,
+            charms: QueryInfo::find_category(SearchCategory::Charms).execute(source)?,
+            decorations: QueryInfo::find_category(SearchCategory::Decorations).execute(source)?,
+            armor_sets: QueryInfo::find_category(SearchCategory::ArmorSets).execute(source)?,
+        })
+    }
+
+    pub fn solve(&self, request: &SetSearchRequest) -> SetSearchResults {
+        let targets = &request.targets;
+
+        // missing points are made up with one decoration per skill: the smallest that has it,
+        // preferring more points at the same size. Bigger jewels of the same skill (e.g. size 4
+        // ones worth two points) are never tried, so sets that only work with them aren't found.
+        let jewels = targets
+            .iter()
+            .map(|target| {
+                self.decorations
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, deco)| {
+                        let rank = deco.skills.iter().find(|rank| rank.skill == target.skill)?;
+                        Some(Jewel {
+                            index,
+                            size: deco.slot,
+                            points: rank.level,
+                        })
+                    })
+                    .min_by(|a, b| a.size.cmp(&b.size).then(b.points.cmp(&a.points)))
+            })
+            .collect::<Vec<_>>();
+
+        // sets whose bonus helps with a target, and the most each target can get from bonuses
+        let mut fixed_bound = targets
+            .iter()
+            .enumerate()
+            .map(|(idx, _)| slot_potential(&request.weapon_slots, jewels[idx].as_ref()))
+            .collect::<Vec<_>>();
+        let mut bonus_sets = vec![];
+        for set in &self.armor_sets {
+            let ranks = set.bonus.iter().flat_map(|bonus| bonus.ranks.iter());
+            for rank in ranks {
+                if let Some(idx) = target_index(targets, rank.skill.skill) {
+                    fixed_bound[idx] += rank.skill.level;
+                    bonus_sets.push(set.id);
+                }
+            }
+        }
+
+        let mut pieces: Vec<Vec<Candidate>> = ARMOR_TYPES.iter().map(|_| vec![]).collect();
+        for armor in &self.armor {
+            if request.rank.is_some_and(|rank| rank != armor.rank) {
+                continue;
+            }
+            let type_idx = match ARMOR_TYPES.iter().position(|t| *t == armor.type_val) {
+                Some(idx) => idx,
+                None => continue,
+            };
+
+            let mut slots = armor.slots.iter().map(|slot| slot.rank).collect::<Vec<_>>();
+            slots.sort_by(|a, b| b.cmp(a));
+            let mut points = vec![0; targets.len()];
+            let mut extra = vec![];
+            for rank in &armor.skills {
+                match target_index(targets, rank.skill) {
+                    Some(idx) => points[idx] += rank.level,
+                    None => extra.push((rank.skill, rank.skill_name.clone(), rank.level)),
+                }
+            }
+            let potential = points
+                .iter()
+                .enumerate()
+                .map(|(idx, points)| points + slot_potential(&slots, jewels[idx].as_ref()))
+                .collect();
+            let set = armor.armor_set.as_ref().map(|set| set.id);
+
+            pieces[type_idx].push(Candidate {
+                piece: FoundPiece {
+                    id: armor.id,
+                    name: armor.name.clone(),
+                },
+                defense: armor.defense.max,
+                points,
+                potential,
+                slots,
+                set,
+                bonus_set: set.filter(|id| bonus_sets.contains(id)),
+                extra,
+            });
+        }
+        let pieces = pieces
+            .into_iter()
+            .map(|candidates| prune_dominated(candidates, request.order))
+            .collect::<Vec<_>>();
+
+        // a charm's last rank has the most of every skill, so only that one is considered
+        let mut charms = vec![];
+        let mut charm_bound = vec![0; targets.len()];
+        for charm in &self.charms {
+            let rank = match charm.ranks.last() {
+                Some(rank) => rank,
+                None => continue,
+            };
+            let mut helps = false;
+            for skill in &rank.skills {
+                if let Some(idx) = target_index(targets, skill.skill) {
+                    charm_bound[idx] = charm_bound[idx].max(skill.level);
+                    helps = true;
+                }
+            }
+            if helps {
+                let piece = FoundPiece {
+                    id: charm.id,
+                    name: rank.name.clone(),
+                };
+                charms.push((piece, rank.skills.as_slice()));
+            }
+        }
+
+        // what the armor types from each depth onwards could add at best
+        let mut remaining_bound = vec![vec![0; targets.len()]; pieces.len() + 1];
+        let mut remaining_defense = vec![0; pieces.len() + 1];
+        for depth in (0..pieces.len()).rev() {
+            let below = remaining_bound[depth + 1].clone();
+            for (idx, (bound, below)) in remaining_bound[depth].iter_mut().zip(below).enumerate() {
+                let best = pieces[depth]
+                    .iter()
+                    .map(|candidate| candidate.potential[idx])
+                    .max()
+                    .unwrap_or(0);
+                *bound = below + best;
+            }
+            let best_defense = pieces[depth]
+                .iter()
+                .map(|candidate| candidate.defense)
+                .max()
+                .unwrap_or(0);
+            remaining_defense[depth] = remaining_defense[depth + 1] + best_defense;
+        }
+
+        let mut search = Search {
+            request,
+            data: self,
+            jewels,
+            pieces,
+            charms,
+            charm_bound,
+            fixed_bound,
+            remaining_bound,
+            remaining_defense,
+            chosen: vec![],
+            found: vec![],
+            checked: 0,
+        };
+        search.visit(&vec![0; targets.len()], 0);
+
+        SetSearchResults {
+            complete: search.checked < MAX_CHECKED_SETS,
+            sets: search.found,
+        }
+    }
+}
+
+impl<'a> Search<'a> {
+    fn visit(&mut self, potential: &[i32], defense: i32) {
+        if self.checked >= MAX_CHECKED_SETS {
+            return;
+        }
+        self.checked += 1;
+
+        let depth = self.chosen.len();
+        let reachable = self
+            .request
+            .targets
+            .iter()
+            .enumerate()
+            .all(|(idx, target)| {
+                potential[idx]
+                    + self.remaining_bound[depth][idx]
+                    + self.charm_bound[idx]
+                    + self.fixed_bound[idx]
+                    >= target.level
+            });
+        if !reachable {
+            return;
+        }
+        if self.request.order == SetSearchOrder::Defense && self.found.len() >= MAX_FOUND_SETS {
+            let worst = self.found.last().map_or(0, |set| set.defense);
+            if defense + self.remaining_defense[depth] < worst {
+                return;
+            }
+        }
+
+        if depth == self.pieces.len() {
+            // the empty charm is always worth a try
+            for charm in 0..=self.charms.len() {
+                if let Some(set) = self.evaluate(charm.checked_sub(1)) {
+                    self.keep(set);
+                }
+            }
+            return;
+        }
+
+        for idx in 0..self.pieces[depth].len() {
+            let next_potential = potential
+                .iter()
+                .zip(&self.pieces[depth][idx].potential)
+                .map(|(a, b)| a + b)
+                .collect::<Vec<_>>();
+            let next_defense = defense + self.pieces[depth][idx].defense;
+            self.chosen.push(idx);
+            self.visit(&next_potential, next_defense);
+            self.chosen.pop();
+        }
+    }
+
+    // Works out the full set for the chosen pieces and charm, or None if it misses a target
+    fn evaluate(&self, charm: Option<usize>) -> Option<FoundSet> {
+        let targets = &self.request.targets;
+        let mut tally = SkillTally::new(targets);
+
+        let chosen = self
+            .chosen
+            .iter()
+            .enumerate()
+            .map(|(depth, idx)| &self.pieces[depth][*idx])
+            .collect::<Vec<_>>();
+        let mut slots = self.request.weapon_slots.clone();
+        let mut set_pieces: BTreeMap<i32, i32> = BTreeMap::new();
+        for candidate in &chosen {
+            for (idx, level) in candidate.points.iter().enumerate() {
+                tally.points[idx] += level;
+            }
+            for (skill, name, level) in &candidate.extra {
+                tally.add(*skill, name, *level);
+            }
+            slots.extend(candidate.slots.iter());
+            if let Some(set) = candidate.set {
+                *set_pieces.entry(set).or_insert(0) += 1;
+            }
+        }
+        for (set_id, count) in set_pieces {
+            let set = self.data.armor_sets.iter().find(|set| set.id == set_id);
+            let ranks = set
+                .and_then(|set| set.bonus.as_ref())
+                .map(|bonus| bonus.ranks.as_slice())
+                .unwrap_or_default();
+            for rank in ranks.iter().filter(|rank| rank.pieces <= count) {
+                tally.add(rank.skill.skill, &rank.skill.skill_name, rank.skill.level);
+            }
+        }
+        let charm = charm.map(|idx| &self.charms[idx]);
+        if let Some((_, skills)) = charm {
+            for rank in skills.iter() {
+                tally.add(rank.skill, &rank.skill_name, rank.level);
+            }
+        }
+
+        // fill the biggest decorations first, each into the smallest slot that fits it
+        let mut needed = vec![];
+        for (idx, target) in targets.iter().enumerate() {
+            let missing = target.level - tally.points[idx];
+            if missing <= 0 {
+                continue;
+            }
+            let jewel = self.jewels[idx].as_ref()?;
+            let count = (missing + jewel.points - 1) / jewel.points;
+            needed.extend((0..count).map(|_| jewel));
+        }
+        needed.sort_by_key(|jewel| Reverse(jewel.size));
+        slots.sort();
+        let mut decorations = vec![];
+        for jewel in needed {
+            let slot = slots.iter().position(|size| *size >= jewel.size)?;
+            slots.remove(slot);
+
+            let deco = &self.data.decorations[jewel.index];
+            // the targets were already counted when working out how many to use
+            for rank in deco.skills.iter() {
+                if target_index(targets, rank.skill).is_none() {
+                    tally.add(rank.skill, &rank.skill_name, rank.level);
+                }
+            }
+            decorations.push(FoundPiece {
+                id: deco.id,
+                name: deco.name.clone(),
+            });
+        }
+        slots.reverse();
+
+        Some(FoundSet {
+            armor: chosen
+                .iter()
+                .map(|candidate| candidate.piece.clone())
+                .collect(),
+            charm: charm.map(|(piece, _)| piece.clone()),
+            decorations,
+            defense: chosen.iter().map(|candidate| candidate.defense).sum(),
+            free_slots: slots,
+            extra_skills: tally.extra.into_values().collect(),
+        })
+    }
+
+    // Inserts the set in rank order, dropping whatever falls off the end
+    fn keep(&mut self, set: FoundSet) {
+        let order = self.request.order;
+        let position = self
+            .found
+            .iter()
+            .position(|other| set.compare(other, order) == Ordering::Less)
+            .unwrap_or(self.found.len());
+        if position < MAX_FOUND_SETS {
+            self.found.insert(position, set);
+            self.found.truncate(MAX_FOUND_SETS);
+        }
+    }
+}
+
+//
+// Set Search Window
+//
+#[derive(Debug)]
+pub struct SetSearchState {
+    skill_idx: i32,
+    level: i32,
+    targets: Vec<SkillTarget>,
+    rank_idx: i32,
+    order_idx: i32,
+    searching: bool,
+    results: Option<SetSearchResults>,
+}
+
+impl Default for SetSearchState {
+    fn default() -> Self {
+        Self {
+            skill_idx: 0,
+            level: 1,
+            targets: vec![],
+            rank_idx: 2, // High Rank
+            order_idx: 0,
+            searching: false,
+            results: None,
+        }
+    }
+}
+
+impl SetSearchState {
+    pub fn set_results(&mut self, results: Option<SetSearchResults>) {
+        self.searching = false;
+        self.results = results;
+    }
+
+    fn request(&self) -> SetSearchRequest {
+        SetSearchRequest {
+            targets: self.targets.clone(),
+            rank: match self.rank_idx {
+                1 => Some(ArmorRank::Low),
+                2 => Some(ArmorRank::High),
+                _ => None,
+            },
+            weapon_slots: vec![],
+            order: match self.order_idx {
+                1 => SetSearchOrder::FreeSlots,
+                2 => SetSearchOrder::ExtraSkills,
+                _ => SetSearchOrder::Defense,
+            },
+        }
+    }
+
+    fn layout_targets<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        let skills = match details
+            .catalog
+            .skills
+            .get_or_request(event_queue, MhwEvent::LoadSkills)
+        {
+            Some(skills) => skills,
+            None => {
+                ui.text("Loading skills...");
+                return;
+            }
+        };
+
+        let skill_names = skills
+            .iter()
+            .map(|skill| ImString::new(skill.name.as_str()))
+            .collect::<Vec<_>>();
+        let ref_names = skill_names
+            .iter()
+            .map(std::convert::AsRef::as_ref)
+            .collect::<Vec<_>>();
+        ui.with_item_width(200.0, || {
+            ui.combo(
+                im_str!("##skill_combo"),
+                &mut self.skill_idx,
+                ref_names.as_slice(),
+                20,
+            );
+        });
+
+        let picked = skills.get(self.skill_idx as usize);
+        let max_level = picked.map_or(1, |skill| skill.max_level().max(1));
+        self.level = self.level.max(1).min(max_level);
+        ui.same_line(0.0);
+        ui.with_item_width(100.0, || {
+            ui.slider_int(im_str!("##level_slider"), &mut self.level, 1, max_level)
+                .build();
+        });
+        ui.same_line(0.0);
+        if ui.button(im_str!("Add Skill"), (0.0, 0.0)) {
+            if let Some(skill) = picked {
+                self.targets.retain(|target| target.skill != skill.id);
+                self.targets.push(SkillTarget {
+                    skill: skill.id,
+                    name: skill.name.clone(),
+                    level: self.level,
+                });
+            }
+        }
+
+        let mut removed = None;
+        for (idx, target) in self.targets.iter().enumerate() {
+            ui.with_id(idx as i32, || {
+                if ui.small_button(im_str!("x")) {
+                    removed = Some(idx);
+                }
+                ui.same_line(0.0);
+                ui.text(format!("{} Lv {}", target.name, target.level));
+            });
+        }
+        if let Some(idx) = removed {
+            self.targets.remove(idx);
+        }
+    }
+
+    fn layout_results<'a>(&self, ui: &Ui<'a>, event_queue: &mut VecDeque<MhwEvent>) {
+        let results = match &self.results {
+            Some(results) => results,
+            None => return,
+        };
+        // the solver only knows each skill's smallest decoration, see SetSearchData::solve
+        ui.text_wrapped(im_str!(
+            "Missing skill points are only made up with each skill's smallest decoration, \
+             so sets that need a bigger one aren't found"
+        ));
+        if results.sets.is_empty() {
+            ui.text("No sets found");
+            return;
+        }
+        if !results.complete {
+            ui.text("Search stopped early; add more skills to narrow it down");
+        }
+
+        let open_entry = |event_queue: &mut VecDeque<MhwEvent>, category, piece: &FoundPiece| {
+            event_queue.push_back(MhwEvent::RequestEntry(EntryRequest::new(
+                category, piece.id,
+            )));
+        };
+        for (idx, set) in results.sets.iter().enumerate() {
+            ui.with_id(idx as i32, || {
+                let free_slots = set
+                    .free_slots
+                    .iter()
+                    .map(|size| size.to_string())
+                    .collect::<Vec<_>>();
+                let header = ImString::from(format!(
+                    "#{}: {} Defense, Free Slots [{}]",
+                    idx + 1,
+                    set.defense,
+                    free_slots.join(" ")
+                ));
+                if !ui.collapsing_header(&header).build() {
+                    return;
+                }
+
+                ui.indent(10.0);
+                for piece in &set.armor {
+                    if ui.small_button(&ImString::new(piece.name.as_str())) {
+                        open_entry(event_queue, SearchCategory::Armor, piece);
+                    }
+                }
+                if let Some(charm) = &set.charm {
+                    if ui.small_button(&ImString::new(charm.name.as_str())) {
+                        open_entry(event_queue, SearchCategory::Charms, charm);
+                    }
+                }
+                for deco in &set.decorations {
+                    if ui.small_button(&ImString::new(deco.name.as_str())) {
+                        open_entry(event_queue, SearchCategory::Decorations, deco);
+                    }
+                }
+                if !set.extra_skills.is_empty() {
+                    let extra = set
+                        .extra_skills
+                        .iter()
+                        .map(|(name, level)| format!("{} +{}", name, level))
+                        .collect::<Vec<_>>();
+                    ui.text_wrapped(&ImString::from(format!("Extra: {}", extra.join(", "))));
+                }
+                if ui.button(im_str!("Equip Armor and Charm"), (0.0, 0.0)) {
+                    for piece in &set.armor {
+                        let request = EntryRequest::new(SearchCategory::Armor, piece.id);
+                        event_queue.push_back(MhwEvent::Equip(request));
+                    }
+                    if let Some(charm) = &set.charm {
+                        let request = EntryRequest::new(SearchCategory::Charms, charm.id);
+                        event_queue.push_back(MhwEvent::Equip(request));
+                    }
+                }
+                ui.unindent(10.0);
+            });
+        }
+    }
+}
+
+impl MhwGui for SetSearchState {
+    fn layout<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        if !details.draw_set_search_window {
+            return;
+        }
+
+        let draw_cursor_pos = details.next_start_pos;
+        let mut opened = true;
+        let window = ui
+            .window(im_str!("Armor Set Search"))
+            .position(
+                (draw_cursor_pos.0 + 40.0, draw_cursor_pos.1 + 40.0),
+                ImGuiCond::FirstUseEver,
+            )
+            .size((560.0, 640.0), ImGuiCond::FirstUseEver)
+            .opened(&mut opened)
+            .flags(ImGuiWindowFlags::NoCollapse);
+
+        window.build(|| {
+            ui.with_font(FONT_IDX_NORMAL, || {
+                self.layout_targets(ui, details, event_queue);
+                ui.separator();
+
+                ui.with_item_width(120.0, || {
+                    ui.combo(
+                        im_str!("Rank"),
+                        &mut self.rank_idx,
+                        &[im_str!("Any"), im_str!("Low"), im_str!("High")],
+                        3,
+                    );
+                    ui.same_line(0.0);
+                    ui.combo(
+                        im_str!("Sort By"),
+                        &mut self.order_idx,
+                        &[
+                            im_str!("Defense"),
+                            im_str!("Free Slots"),
+                            im_str!("Extra Skills"),
+                        ],
+                        3,
+                    );
+                });
+                if !self.searching && !self.targets.is_empty() {
+                    if ui.button(im_str!("Search"), (0.0, 0.0)) {
+                        self.searching = true;
+                        self.results = None;
+                        event_queue.push_back(MhwEvent::SearchSets(self.request()));
+                    }
+                    ui.same_line(0.0);
+                    ui.text("(uses the equipped weapon's slots)");
+                }
+                ui.separator();
+
+                if self.searching {
+                    ui.text("Searching...");
+                }
+                self.layout_results(ui, event_queue);
+            });
+        });
+        details.draw_set_search_window = opened;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::data_source::fixture_source;
+    use super::*;
+
+    const RATHALOS_SET: i32 = 100;
+
+    fn fixture_data() -> SetSearchData {
+        SetSearchData::load(&fixture_source()).unwrap()
+    }
+
+    fn request(targets: &[(i32, i32)], order: SetSearchOrder) -> SetSearchRequest {
+        SetSearchRequest {
+            targets: targets
+                .iter()
+                .map(|(skill, level)| SkillTarget {
+                    skill: *skill,
+                    name: format!("Skill {}", skill),
+                    level: *level,
+                })
+                .collect(),
+            rank: Some(ArmorRank::High),
+            weapon_slots: vec![],
+            order,
+        }
+    }
+
+    // Adds up a skill over everything in the set, the long way round
+    fn skill_level(data: &SetSearchData, set: &FoundSet, skill: i32) -> i32 {
+        let mut level = 0;
+        let mut rathalos_pieces = 0;
+        for piece in &set.armor {
+            let armor = data
+                .armor
+                .iter()
+                .find(|armor| armor.id == piece.id)
+                .unwrap();
+            let ranks = armor.skills.iter().filter(|rank| rank.skill == skill);
+            level += ranks.map(|rank| rank.level).sum::<i32>();
+            if armor.armor_set.as_ref().map(|set| set.id) == Some(RATHALOS_SET) {
+                rathalos_pieces += 1;
+            }
+        }
+        if let Some(piece) = &set.charm {
+            let charm = data
+                .charms
+                .iter()
+                .find(|charm| charm.id == piece.id)
+                .unwrap();
+            let ranks = charm.ranks.last().unwrap().skills.iter();
+            level += ranks
+                .filter(|rank| rank.skill == skill)
+                .map(|rank| rank.level)
+                .sum::<i32>();
+        }
+        for piece in &set.decorations {
+            let deco = data.decorations.iter().find(|d| d.id == piece.id).unwrap();
+            let ranks = deco.skills.iter().filter(|rank| rank.skill == skill);
+            level += ranks.map(|rank| rank.level).sum::<i32>();
+        }
+        // Rathalos Mastery grants Critical Element at three pieces
+        if skill == 5 && rathalos_pieces >= 3 {
+            level += 1;
+        }
+        level
+    }
+
+    fn candidate(
+        id: i32,
+        defense: i32,
+        points: Vec<i32>,
+        slots: Vec<i32>,
+        bonus_set: Option<i32>,
+    ) -> Candidate {
+        Candidate {
+            piece: FoundPiece {
+                id,
+                name: format!("Piece {}", id),
+            },
+            defense,
+            potential: points.clone(),
+            points,
+            slots,
+            set: bonus_set,
+            bonus_set,
+            extra: vec![],
+        }
+    }
+
+    #[test]
+    fn dominated_pieces_are_pruned() {
+        let strong = candidate(1, 80, vec![2], vec![2], None);
+        let weak = candidate(2, 70, vec![1], vec![1], None);
+        let slotted = candidate(3, 60, vec![0], vec![3, 1], None);
+        let bonus = candidate(4, 50, vec![0], vec![], Some(RATHALOS_SET));
+        let order = SetSearchOrder::Defense;
+        assert!(strong.dominates(&weak, order));
+        assert!(!weak.dominates(&strong, order));
+        assert!(!strong.dominates(&slotted, order));
+        // a piece counting towards a wanted set bonus is only beaten by one of the same set
+        assert!(!strong.dominates(&bonus, order));
+
+        let kept = prune_dominated(vec![weak, slotted, bonus, strong], order);
+        let ids = kept.iter().map(|c| c.piece.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn extra_skills_keep_weaker_pieces() {
+        let strong = candidate(1, 80, vec![2], vec![2], None);
+        let mut extra = candidate(2, 70, vec![1], vec![1], None);
+        extra.extra = vec![(9, "Skill 9".to_owned(), 2)];
+        assert!(strong.dominates(&extra, SetSearchOrder::Defense));
+        assert!(!strong.dominates(&extra, SetSearchOrder::ExtraSkills));
+
+        let kept = prune_dominated(vec![extra, strong], SetSearchOrder::ExtraSkills);
+        let ids = kept.iter().map(|c| c.piece.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn found_sets_reach_every_target() {
+        let data = fixture_data();
+        let targets = [(1, 3), (3, 2)];
+        let results = data.solve(&request(&targets, SetSearchOrder::Defense));
+        assert!(results.complete);
+        assert!(!results.sets.is_empty());
+        for set in &results.sets {
+            assert_eq!(set.armor.len(), ARMOR_TYPES.len());
+            for (skill, level) in targets.iter() {
+                assert!(skill_level(&data, set, *skill) >= *level, "{:?}", set);
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_targets_find_nothing() {
+        let data = fixture_data();
+
+        // Weakness Exploit tops out at 5 from armor and three size 2+ slots
+        let results = data.solve(&request(&[(3, 6)], SetSearchOrder::Defense));
+        assert!(results.complete);
+        assert!(results.sets.is_empty());
+
+        // Health Boost has no decoration and not enough on armor
+        let results = data.solve(&request(&[(4, 5)], SetSearchOrder::Defense));
+        assert!(results.sets.is_empty());
+
+        // a weapon slot makes up the missing point
+        let mut with_weapon = request(&[(3, 6)], SetSearchOrder::Defense);
+        with_weapon.weapon_slots = vec![2];
+        let results = data.solve(&with_weapon);
+        assert_eq!(results.sets.len(), 1);
+        assert_eq!(results.sets[0].decorations.len(), 4);
+    }
+
+    #[test]
+    fn set_bonus_needs_enough_pieces() {
+        let data = fixture_data();
+        let results = data.solve(&request(&[(5, 1)], SetSearchOrder::Defense));
+        assert!(!results.sets.is_empty());
+        for set in &results.sets {
+            let pieces = set
+                .armor
+                .iter()
+                .filter(|piece| [10, 12, 14, 15, 16].contains(&piece.id))
+                .count();
+            assert!(pieces >= 3, "{:?}", set);
+        }
+
+        // the Kaiser Crown is the better head as long as three Rathalos pieces are worn
+        let best = &results.sets[0];
+        assert_eq!(best.defense, 404);
+        assert_eq!(best.armor[0].name, "Kaiser Crown Beta");
+    }
+
+    #[test]
+    fn decorations_fill_the_biggest_first() {
+        let data = fixture_data();
+        let results = data.solve(&request(&[(3, 5), (2, 4)], SetSearchOrder::Defense));
+        assert_eq!(results.sets.len(), 1);
+
+        let set = &results.sets[0];
+        let decorations = set.decorations.iter().map(|d| d.id).collect::<Vec<_>>();
+        assert_eq!(decorations, vec![203, 203, 203, 202]);
+        // the size 3 slot takes a size 2 jewel once both size 2 slots are full
+        assert_eq!(set.free_slots, vec![1]);
+        assert_eq!(set.charm.as_ref().map(|c| c.id), None);
+    }
+
+    #[test]
+    fn results_follow_the_chosen_order() {
+        let data = fixture_data();
+        let targets = [(1, 2)];
+        type Key = fn(&FoundSet) -> i32;
+        let orders: [(SetSearchOrder, Key); 3] = [
+            (SetSearchOrder::Defense, |set| set.defense),
+            (SetSearchOrder::FreeSlots, |set| set.free_slot_total()),
+            (SetSearchOrder::ExtraSkills, |set| set.extra_skill_total()),
+        ];
+        for (order, key) in orders.iter() {
+            let results = data.solve(&request(&targets, *order));
+            assert!(results.sets.len() > 1);
+            for pair in results.sets.windows(2) {
+                assert!(key(&pair[0]) >= key(&pair[1]), "{:?}", order);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::common::{fonts::*, GuiDetails, MhwEvent, MhwWindowContents, SkillRank};
+use crate::widgets::table_view::*;
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillInfo {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub ranks: Vec<SkillRank>,
+
+    // internal details
+    #[serde(skip)]
+    ranks_cache: SimpleTableDataModel,
+}
+
+impl SkillInfo {
+    pub fn max_level(&self) -> i32 {
+        self.ranks.iter().map(|rank| rank.level).max().unwrap_or(0)
+    }
+
+    fn ranks_data(&mut self) -> &TableDataModel {
+        if self.ranks_cache.is_empty() {
+            self.ranks_cache.set_columns(3);
+            let ranks = &self.ranks;
+            for rank in ranks {
+                self.ranks_cache.push(format!("Lv {}", rank.level));
+                self.ranks_cache.push(rank.description.clone());
+                self.ranks_cache.push(rank.modifiers.summary());
+            }
+        }
+        &self.ranks_cache
+    }
+}
+
+impl MhwWindowContents for SkillInfo {
+    fn build_window<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        _details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        //=======================================
+        // Name/ID
+        ui.with_font(FONT_IDX_HEADER, || {
+            let imstring = ImString::new(self.name.as_str());
+            ui.text(&imstring);
+        });
+        ui.with_font(FONT_IDX_MINI, || {
+            let id_string = format!("id: [{}]", self.id);
+            ui.text(id_string.as_str());
+            ui.same_line(0.0);
+            ui.text(format!("Max Level {}", self.max_level()));
+        });
+
+        // Description
+        ui.with_font(FONT_IDX_WINDOW_TITLE, || {
+            let text = im_str!("Description:");
+            ui.text(text);
+        });
+        ui.with_font(FONT_IDX_NORMAL, || {
+            let imstring = ImString::new(self.description.as_str());
+            ui.text_wrapped(&imstring);
+        });
+
+        //=======================================
+        // Lists section
+        ui.separator();
+        draw_table(ui, "Ranks", self.ranks_data(), event_queue);
+    }
+}
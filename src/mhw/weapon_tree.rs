@@ -0,0 +1,282 @@
+use super::common::{merge_costs, rarity::*, CraftingCost, MhwEvent};
+use super::data_source::DataSource;
+use super::entry_display::EntryRequest;
+use super::query::{MHWQueryError, QueryFilter, QueryFilterType, QueryInfo, QueryProjection};
+use super::search::SearchCategory;
+use super::weapons::{WeaponCraftingInfo, WeaponType};
+use imgui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+
+const TREE_INDENT: f32 = 14.0;
+
+//
+// Weapon Tree Node
+//
+// One weapon of a type's upgrade tree; `crafting` links it to its parent and branches
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WeaponTreeNode {
+    pub id: i32,
+    pub name: String,
+    pub rarity: u32,
+    pub crafting: WeaponCraftingInfo,
+}
+
+// Trees are loaded a whole weapon type at a time, so only what the nodes use is fetched
+pub fn load_weapon_tree(
+    source: &dyn DataSource,
+    weapon_type: WeaponType,
+) -> Result<Vec<WeaponTreeNode>, MHWQueryError> {
+    QueryInfo::find_category(SearchCategory::Weapons)
+        .with_filter(QueryFilter::new(
+            "type".to_owned(),
+            QueryFilterType::Exact(weapon_type.api_name().into()),
+        ))
+        .with_projection(QueryProjection::including(vec![
+            "id", "name", "rarity", "crafting",
+        ]))
+        .execute(source)
+}
+
+//
+// Weapon Tree
+//
+// Every weapon of one type, linked up through `previous` and `branches`
+#[derive(Debug, Clone, Copy)]
+pub struct WeaponTree<'a> {
+    nodes: &'a [WeaponTreeNode],
+}
+
+impl<'a> WeaponTree<'a> {
+    pub fn new(nodes: &'a [WeaponTreeNode]) -> Self {
+        Self { nodes }
+    }
+
+    pub fn node(&self, id: i32) -> Option<&'a WeaponTreeNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn name(&self, id: i32) -> Option<&'a str> {
+        self.node(id).map(|node| node.name.as_str())
+    }
+
+    // Weapons that don't upgrade from anything in the tree
+    pub fn roots(&self) -> impl Iterator<Item = &'a WeaponTreeNode> + 'a {
+        let tree = *self;
+        self.nodes.iter().filter(move |node| {
+            node.crafting
+                .previous
+                .is_none_or(|previous| tree.node(previous).is_none())
+        })
+    }
+
+    pub fn children(
+        &self,
+        node: &'a WeaponTreeNode,
+    ) -> impl Iterator<Item = &'a WeaponTreeNode> + 'a {
+        let tree = *self;
+        node.crafting
+            .branches
+            .iter()
+            .filter_map(move |id| tree.node(*id))
+    }
+
+    // The weapons from the root of the tree down to `id`, inclusive
+    pub fn path_to(&self, id: i32) -> Vec<&'a WeaponTreeNode> {
+        let mut path = vec![];
+        let mut next = self.node(id);
+        while let Some(node) = next {
+            // guards against bad data linking a weapon back to itself
+            if path.iter().any(|seen: &&WeaponTreeNode| seen.id == node.id) {
+                break;
+            }
+            path.push(node);
+            next = node
+                .crafting
+                .previous
+                .and_then(|previous| self.node(previous));
+        }
+        path.reverse();
+        path
+    }
+
+    // The path to `id` from the closest weapon on it that can be crafted outright
+    pub fn upgrade_path(&self, id: i32) -> Vec<&'a WeaponTreeNode> {
+        let mut path = self.path_to(id);
+        if let Some(start) = path.iter().rposition(|node| node.crafting.craftable) {
+            path.drain(..start);
+        }
+        path
+    }
+
+    // Everything needed to craft the start of the upgrade path and then upgrade along it
+    pub fn upgrade_cost(&self, id: i32) -> Vec<CraftingCost> {
+        let path = self.upgrade_path(id);
+        let crafting = path
+            .first()
+            .into_iter()
+            .flat_map(|node| node.crafting.crafting_materials.iter());
+        let upgrades = path
+            .iter()
+            .skip(1)
+            .flat_map(|node| node.crafting.upgrade_materials.iter());
+        merge_costs(crafting.chain(upgrades))
+    }
+}
+
+fn draw_branch<'a>(
+    ui: &Ui<'a>,
+    tree: WeaponTree,
+    node: &WeaponTreeNode,
+    current: i32,
+    expanded: &mut BTreeSet<i32>,
+    event_queue: &mut VecDeque<MhwEvent>,
+) {
+    ui.with_id(node.id, || {
+        let is_expanded = expanded.contains(&node.id);
+        if node.crafting.branches.is_empty() {
+            ui.text("   ");
+        } else {
+            let toggle = if is_expanded {
+                im_str!("-")
+            } else {
+                im_str!("+")
+            };
+            if ui.small_button(toggle) {
+                if is_expanded {
+                    expanded.remove(&node.id);
+                } else {
+                    expanded.insert(node.id);
+                }
+            }
+        }
+        ui.same_line(0.0);
+
+        let label = ImString::new(node.name.as_str());
+        ui.with_color_var(ImGuiCol::Text, rarity_color(node.rarity), || {
+            if ui.selectable(
+                &label,
+                node.id == current,
+                ImGuiSelectableFlags::empty(),
+                (0.0, 0.0),
+            ) {
+                let request = EntryRequest::new(SearchCategory::Weapons, node.id);
+                event_queue.push_back(MhwEvent::RequestEntry(request));
+            }
+        });
+
+        if is_expanded {
+            ui.indent(TREE_INDENT);
+            for child in tree.children(node) {
+                draw_branch(ui, tree, child, current, expanded, event_queue);
+            }
+            ui.unindent(TREE_INDENT);
+        }
+    });
+}
+
+// Draws the whole tree as expandable rows; clicking a weapon opens it
+pub fn draw_weapon_tree<'a>(
+    ui: &Ui<'a>,
+    tree: WeaponTree,
+    current: i32,
+    expanded: &mut BTreeSet<i32>,
+    event_queue: &mut VecDeque<MhwEvent>,
+) {
+    for root in tree.roots() {
+        draw_branch(ui, tree, root, current, expanded, event_queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::data_source::fixture_source;
+    use super::super::items::ItemInfo;
+    use super::*;
+
+    fn fixture_nodes() -> Vec<WeaponTreeNode> {
+        load_weapon_tree(&fixture_source(), WeaponType::GreatSword).unwrap()
+    }
+
+    fn cost(item: i32, quantity: i32) -> CraftingCost {
+        let mut info = ItemInfo::default();
+        info.id = item;
+        CraftingCost {
+            quantity,
+            item: info,
+        }
+    }
+
+    fn node(id: i32, craftable: bool, previous: Option<i32>) -> WeaponTreeNode {
+        WeaponTreeNode {
+            id,
+            name: format!("Weapon {}", id),
+            rarity: 1,
+            crafting: WeaponCraftingInfo {
+                craftable,
+                previous,
+                branches: vec![],
+                crafting_materials: vec![cost(1, 1)],
+                upgrade_materials: vec![cost(2, id)],
+            },
+        }
+    }
+
+    fn ids(path: &[&WeaponTreeNode]) -> Vec<i32> {
+        path.iter().map(|node| node.id).collect()
+    }
+
+    fn quantities(costs: &[CraftingCost]) -> Vec<(i32, i32)> {
+        costs
+            .iter()
+            .map(|cost| (cost.item.id, cost.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn paths_start_at_the_closest_craftable_weapon() {
+        let nodes = fixture_nodes();
+        let tree = WeaponTree::new(&nodes);
+        assert_eq!(ids(&tree.roots().collect::<Vec<_>>()), vec![401]);
+        assert_eq!(ids(&tree.path_to(403)), vec![401, 402, 403]);
+        assert_eq!(ids(&tree.upgrade_path(402)), vec![401, 402]);
+        assert_eq!(ids(&tree.upgrade_path(403)), vec![403]);
+        assert!(tree.upgrade_path(999).is_empty());
+    }
+
+    #[test]
+    fn upgrade_cost_crafts_the_start_then_upgrades() {
+        let nodes = fixture_nodes();
+        let tree = WeaponTree::new(&nodes);
+        // Iron Ore from crafting and upgrading is added up
+        assert_eq!(
+            quantities(&tree.upgrade_cost(402)),
+            vec![(107, 5), (103, 1)]
+        );
+        assert_eq!(
+            quantities(&tree.upgrade_cost(403)),
+            vec![(101, 2), (102, 1)]
+        );
+    }
+
+    #[test]
+    fn uncraftable_roots_keep_the_whole_path() {
+        let nodes = vec![node(1, false, None), node(2, false, Some(1))];
+        let tree = WeaponTree::new(&nodes);
+        assert_eq!(ids(&tree.upgrade_path(2)), vec![1, 2]);
+        assert_eq!(quantities(&tree.upgrade_cost(2)), vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn cycles_stop_the_path() {
+        let nodes = vec![node(1, false, Some(2)), node(2, false, Some(1))];
+        let tree = WeaponTree::new(&nodes);
+        assert_eq!(ids(&tree.path_to(1)), vec![2, 1]);
+        assert_eq!(ids(&tree.upgrade_path(2)), vec![1, 2]);
+        assert_eq!(tree.roots().count(), 0);
+
+        let looped = vec![node(3, true, Some(3))];
+        let tree = WeaponTree::new(&looped);
+        assert_eq!(ids(&tree.upgrade_path(3)), vec![3]);
+    }
+}
@@ -1,6 +1,11 @@
 use super::common::{
     fonts::*, rarity::*, CraftingCost, Element, GuiDetails, MhwEvent, MhwWindowContents, Slot,
 };
+use super::decorations::draw_slots;
+use super::entry_display::EntryRequest;
+use super::search::SearchCategory;
+use super::weapon_tree::{draw_weapon_tree, WeaponTree};
+use super::wishlist::WishlistItem;
 use crate::widgets::table_view::*;
 use imgui::*;
 use onig::*;
@@ -8,11 +13,11 @@ use serde::de;
 use serde::de::Visitor;
 use serde::Deserializer;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
 use std::collections::VecDeque;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Display};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "kebab-case")]
 pub enum WeaponType {
     GreatSword,
@@ -31,6 +36,28 @@ pub enum WeaponType {
     Bow,
 }
 
+impl WeaponType {
+    // The name the API uses for the type, e.g. for filtering by it
+    pub fn api_name(&self) -> &'static str {
+        match self {
+            WeaponType::GreatSword => "great-sword",
+            WeaponType::LongSword => "long-sword",
+            WeaponType::SwordAndShield => "sword-and-shield",
+            WeaponType::DualBlades => "dual-blades",
+            WeaponType::Hammer => "hammer",
+            WeaponType::HuntingHorn => "hunting-horn",
+            WeaponType::Lance => "lance",
+            WeaponType::Gunlance => "gunlance",
+            WeaponType::SwitchAxe => "switch-axe",
+            WeaponType::ChargeBlade => "charge-blade",
+            WeaponType::InsectGlaive => "insect-glaive",
+            WeaponType::LightBowgun => "light-bowgun",
+            WeaponType::HeavyBowgun => "heavy-bowgun",
+            WeaponType::Bow => "bow",
+        }
+    }
+}
+
 impl Display for WeaponType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -281,17 +308,7 @@ pub enum PhialType {
 }
 
 fn calc_power(parts: &Captures) -> Option<i32> {
-    if let Some(result) = parts.at(2) {
-        if let Ok(val) = result.parse::<i32>() {
-            Some(val)
-        } else {
-            //println!("{:?}", parts);
-            None
-        }
-    } else {
-        //println!("{:?}", parts);
-        None
-    }
+    parts.at(2)?.parse::<i32>().ok()
 }
 
 impl<'de> Deserialize<'de> for PhialType {
@@ -323,29 +340,26 @@ impl<'de> Deserialize<'de> for PhialType {
             where
                 E: de::Error,
             {
-                println!("Parsing value: {}", value);
                 // create the regex
                 // (\D+)[\s]?(\d+)?
                 // ([^0-9]+)([0-9]+)?
                 let re = if let Ok(result) = Regex::new(r"(\D+)\s?(?!\d+)\D?(\d+)?") {
                     result
                 } else {
-                    return Err(de::Error::unknown_field(value, FIELDS));
+                    return Err(de::Error::unknown_variant(value, FIELDS));
                 };
 
                 // split the string
                 let parts = if let Some(result) = re.captures(value) {
                     result
                 } else {
-                    //println!("{:?}", re.captures(value));
-                    return Err(de::Error::unknown_field(value, FIELDS));
+                    return Err(de::Error::unknown_variant(value, FIELDS));
                 };
 
                 let type_name = if let Some(result) = parts.at(1) {
                     result
                 } else {
-                    //println!("{:?}", parts);
-                    return Err(de::Error::unknown_field(value, FIELDS));
+                    return Err(de::Error::unknown_variant(value, FIELDS));
                 };
 
                 match type_name {
@@ -359,38 +373,31 @@ impl<'de> Deserialize<'de> for PhialType {
                         if let Some(power) = calc_power(&parts) {
                             Ok(PhialType::Dragon(power))
                         } else {
-                            println!("{:?}", parts);
-                            Err(de::Error::unknown_field(value, FIELDS))
+                            Err(de::Error::unknown_variant(value, FIELDS))
                         }
                     }
                     "exhaust" => {
                         if let Some(power) = calc_power(&parts) {
                             Ok(PhialType::Exhaust(power))
                         } else {
-                            println!("{:?}", parts);
-                            Err(de::Error::unknown_field(value, FIELDS))
+                            Err(de::Error::unknown_variant(value, FIELDS))
                         }
                     }
                     "paralysis" => {
                         if let Some(power) = calc_power(&parts) {
                             Ok(PhialType::Paralysis(power))
                         } else {
-                            println!("{:?}", parts);
-                            Err(de::Error::unknown_field(value, FIELDS))
+                            Err(de::Error::unknown_variant(value, FIELDS))
                         }
                     }
                     "poison" => {
                         if let Some(power) = calc_power(&parts) {
                             Ok(PhialType::Poison(power))
                         } else {
-                            println!("{:?}", parts);
-                            Err(de::Error::unknown_field(value, FIELDS))
+                            Err(de::Error::unknown_variant(value, FIELDS))
                         }
                     }
-                    _ => {
-                        println!("{:?}", parts);
-                        Err(de::Error::unknown_field(value, FIELDS))
-                    }
+                    _ => Err(de::Error::unknown_variant(value, FIELDS)),
                 }
             }
         }
@@ -440,17 +447,17 @@ impl<'de> Deserialize<'de> for ShellingType {
                     if let Ok(val) = lvl_num_result {
                         val
                     } else {
-                        return Err(de::Error::unknown_field(value, FIELDS));
+                        return Err(de::Error::unknown_variant(value, FIELDS));
                     }
                 } else {
-                    return Err(de::Error::unknown_field(value, FIELDS));
+                    return Err(de::Error::unknown_variant(value, FIELDS));
                 };
 
                 match label {
                     Some("Normal") => Ok(ShellingType::Normal(lvl_num)),
                     Some("Long") => Ok(ShellingType::Long(lvl_num)),
                     Some("Wide") => Ok(ShellingType::Wide(lvl_num)),
-                    _ => Err(de::Error::unknown_field(value, FIELDS)),
+                    _ => Err(de::Error::unknown_variant(value, FIELDS)),
                 }
             }
         }
@@ -477,7 +484,11 @@ pub enum SpecialAmmoType {
 
 impl Display for SpecialAmmoType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Wyvern {}", self)
+        match self {
+            SpecialAmmoType::Blast => write!(f, "Wyvernblast"),
+            SpecialAmmoType::Heart => write!(f, "Wyvernheart"),
+            SpecialAmmoType::Snipe => write!(f, "Wyvernsnipe"),
+        }
     }
 }
 
@@ -522,6 +533,10 @@ pub struct WeaponInfo {
     upgrade_cache: SimpleTableDataModel,
     #[serde(skip)]
     attributes_cache: SimpleTableDataModel,
+    #[serde(skip)]
+    tree_expanded: Option<BTreeSet<i32>>,
+    #[serde(skip)]
+    path_cost_cache: SimpleTableDataModel,
 }
 
 impl WeaponInfo {
@@ -530,7 +545,8 @@ impl WeaponInfo {
             self.crafting_cache.set_columns(2);
             let mats = &self.crafting.crafting_materials;
             for cost in mats {
-                self.crafting_cache.push(cost.item.name.clone());
+                let link = EntryRequest::new(SearchCategory::Items, cost.item.id);
+                self.crafting_cache.push_link(cost.item.name.clone(), link);
                 self.crafting_cache.push(cost.quantity.to_string());
             }
         }
@@ -542,13 +558,26 @@ impl WeaponInfo {
             self.upgrade_cache.set_columns(2);
             let mats = &self.crafting.upgrade_materials;
             for cost in mats {
-                self.upgrade_cache.push(cost.item.name.clone());
+                let link = EntryRequest::new(SearchCategory::Items, cost.item.id);
+                self.upgrade_cache.push_link(cost.item.name.clone(), link);
                 self.upgrade_cache.push(cost.quantity.to_string());
             }
         }
         &self.upgrade_cache
     }
 
+    fn path_cost_data(&mut self, tree: WeaponTree) -> &TableDataModel {
+        if self.path_cost_cache.is_empty() {
+            self.path_cost_cache.set_columns(2);
+            for cost in tree.upgrade_cost(self.id) {
+                let link = EntryRequest::new(SearchCategory::Items, cost.item.id);
+                self.path_cost_cache.push_link(cost.item.name, link);
+                self.path_cost_cache.push(cost.quantity.to_string());
+            }
+        }
+        &self.path_cost_cache
+    }
+
     fn attribute_data(&mut self) -> &TableDataModel {
         if self.attributes_cache.is_empty() {
             self.attributes_cache.set_columns(2);
@@ -628,8 +657,8 @@ impl MhwWindowContents for WeaponInfo {
     fn build_window<'a>(
         &mut self,
         ui: &Ui<'a>,
-        _details: &mut GuiDetails,
-        _event_queue: &mut VecDeque<MhwEvent>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
     ) {
         //=======================================
         // Name/ID
@@ -637,12 +666,29 @@ impl MhwWindowContents for WeaponInfo {
             let imstring = ImString::new(self.name.as_str());
             ui.text_colored(rarity_color(self.rarity), &imstring);
         });
+        let mut add_to_wishlist = false;
         ui.with_font(FONT_IDX_MINI, || {
             let id_string = format!("id: [{}]", self.id);
             ui.text(id_string.as_str());
             ui.same_line(0.0);
             ui.text(self.type_val.to_string());
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Equip")) {
+                let request = EntryRequest::new(SearchCategory::Weapons, self.id);
+                event_queue.push_back(MhwEvent::Equip(request));
+            }
+            ui.same_line(0.0);
+            add_to_wishlist = ui.small_button(im_str!("Wishlist"));
         });
+        if add_to_wishlist {
+            // the upgrade path comes from the tree; the wishlist fills it in if it isn't loaded yet
+            let tree = details
+                .catalog
+                .weapon_tree(self.type_val, event_queue)
+                .map(WeaponTree::new);
+            let item = WishlistItem::from_weapon(self, tree);
+            event_queue.push_back(MhwEvent::AddToWishlist(item));
+        }
 
         //=======================================
         // Core Stats
@@ -676,33 +722,7 @@ impl MhwWindowContents for WeaponInfo {
             let text = im_str!("Slot Info");
             ui.text(text);
         });
-        for idx in 0..3 {
-            match self.slots.get(idx) {
-                Some(val) => match val.rank {
-                    1 => {
-                        ui.text("[1]");
-                        ui.same_line(0.0);
-                    }
-                    2 => {
-                        ui.text("[2]");
-                        ui.same_line(0.0);
-                    }
-                    3 => {
-                        ui.text("[3]");
-                        ui.same_line(0.0);
-                    }
-                    _ => {
-                        ui.text("[E]");
-                        ui.same_line(0.0);
-                    }
-                },
-                None => {
-                    ui.text("[-]");
-                    ui.same_line(0.0);
-                }
-            }
-        }
-        ui.new_line();
+        draw_slots(ui, &self.slots, details, event_queue);
 
         // Durability
         ui.next_column();
@@ -711,7 +731,7 @@ impl MhwWindowContents for WeaponInfo {
             ui.text(text);
         });
         for durability in &self.durability {
-            durability.draw(&ui);
+            durability.draw(ui);
         }
         {
             let cursor = ui.get_cursor_screen_pos();
@@ -725,20 +745,78 @@ impl MhwWindowContents for WeaponInfo {
         // Maybe don't need elements, as there seems to only ever be 0/1 of them.
         //draw_table(ui, "Elements", &ELEMENTS_COLUMNS, self.elements_data());
         if self.crafting.craftable {
-            draw_table(ui, "Crafting", self.crafting_data());
+            draw_table(ui, "Crafting", self.crafting_data(), event_queue);
         }
 
+        let tree = details
+            .catalog
+            .weapon_tree(self.type_val, event_queue)
+            .map(WeaponTree::new);
         if let Some(previous) = self.crafting.previous {
             ui.text("Upgrade From: ");
             ui.same_line(0.0);
             ui.with_font(FONT_IDX_NORMAL, || {
-                // TODO: once we cache item names, fetch the previous item name from ID
-                ui.text(format!("id [{}]", previous));
+                let name = match tree.and_then(|tree| tree.name(previous)) {
+                    Some(name) => ImString::new(name),
+                    None => ImString::from(format!("id [{}]", previous)),
+                };
+                if ui.small_button(&name) {
+                    let request = EntryRequest::new(SearchCategory::Weapons, previous);
+                    event_queue.push_back(MhwEvent::RequestEntry(request));
+                }
             });
-            draw_table(ui, "Required", self.upgrading_data());
+            draw_table(ui, "Required", self.upgrading_data(), event_queue);
         }
 
         ui.next_column();
-        draw_table(ui, "Attributes", self.attribute_data());
+        draw_table(ui, "Attributes", self.attribute_data(), event_queue);
+
+        //=======================================
+        // Total cost, from the closest craftable weapon
+        ui.columns(1, im_str!("weapon_tree"), false);
+        if let Some(tree) = tree {
+            let path = tree.upgrade_path(self.id);
+            if path.len() > 1 {
+                let names = path
+                    .iter()
+                    .map(|node| node.name.as_str())
+                    .collect::<Vec<_>>();
+                let title = format!("Total Cost from {}", names[0]);
+                draw_table(ui, title.as_str(), self.path_cost_data(tree), event_queue);
+                ui.with_font(FONT_IDX_MINI, || {
+                    ui.text_wrapped(&ImString::from(names.join(" > ")));
+                    ui.text("Zenny costs aren't in the data, so only materials are counted");
+                });
+            }
+        }
+
+        //=======================================
+        // Upgrade tree
+        let header = ImString::from(format!("{} Upgrade Tree", self.type_val));
+        if ui.collapsing_header(&header).build() {
+            match tree {
+                Some(tree) => {
+                    // start with the way to this weapon opened up
+                    let id = self.id;
+                    let expanded = self.tree_expanded.get_or_insert_with(|| {
+                        tree.path_to(id).iter().map(|node| node.id).collect()
+                    });
+                    draw_weapon_tree(ui, tree, self.id, expanded, event_queue);
+                }
+                None => {
+                    let trees = &mut details.catalog.weapon_trees;
+                    let collection = trees.entry(self.type_val).or_default();
+                    if collection.is_pending() {
+                        ui.text("Loading...");
+                    } else {
+                        ui.text("The upgrade tree failed to load");
+                        ui.same_line(0.0);
+                        if ui.small_button(im_str!("Retry")) {
+                            collection.retry();
+                        }
+                    }
+                }
+            }
+        }
     }
 }
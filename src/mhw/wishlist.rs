@@ -0,0 +1,406 @@
+use super::armor::ArmorInfo;
+use super::charms::CharmInfo;
+use super::common::{fonts::*, merge_costs, CraftingCost, GuiDetails, MhwEvent, MhwGui};
+use super::entry_display::EntryRequest;
+use super::query::MHWQueryError;
+use super::search::SearchCategory;
+use super::weapon_tree::WeaponTree;
+use super::weapons::{WeaponInfo, WeaponType};
+use imgui::*;
+use serde_json;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_OWNED_PATH: &str = "mhw_owned.json";
+
+//
+// Wishlist Item
+//
+// One step towards the finished item, e.g. a charm rank or a weapon on the upgrade path
+#[derive(Debug)]
+pub struct WishlistLevel {
+    pub label: String,
+    pub materials: Vec<CraftingCost>,
+}
+
+#[derive(Debug)]
+pub struct WishlistItem {
+    pub category: SearchCategory,
+    pub id: i32,
+    pub name: String,
+    pub levels: Vec<WishlistLevel>,
+    pub target: i32, // index into levels; every level up to and including it is paid for
+    pub pending_tree: Option<WeaponType>, // weapons added before their tree loaded
+}
+
+impl WishlistItem {
+    fn new(category: SearchCategory, id: i32, name: &str, levels: Vec<WishlistLevel>) -> Self {
+        Self {
+            category,
+            id,
+            name: name.to_owned(),
+            target: levels.len() as i32 - 1,
+            levels,
+            pending_tree: None,
+        }
+    }
+
+    // Armor only lists what crafting it costs, so there's just the one level
+    pub fn from_armor(armor: &ArmorInfo) -> Self {
+        let levels = vec![WishlistLevel {
+            label: "Crafted".to_owned(),
+            materials: armor.crafting.materials.clone(),
+        }];
+        Self::new(SearchCategory::Armor, armor.id, armor.name.as_str(), levels)
+    }
+
+    // Levels follow the upgrade path from the closest craftable weapon. Without the tree only
+    // the weapon's own step is known, so the rest is filled in by `set_tree` once it loads.
+    pub fn from_weapon(weapon: &WeaponInfo, tree: Option<WeaponTree>) -> Self {
+        let crafting = &weapon.crafting;
+        let materials = if crafting.craftable {
+            &crafting.crafting_materials
+        } else {
+            &crafting.upgrade_materials
+        };
+        let levels = vec![WishlistLevel {
+            label: weapon.name.clone(),
+            materials: materials.clone(),
+        }];
+        let mut item = Self::new(
+            SearchCategory::Weapons,
+            weapon.id,
+            weapon.name.as_str(),
+            levels,
+        );
+        match tree {
+            Some(tree) => item.set_tree(tree),
+            None => item.pending_tree = Some(weapon.type_val),
+        }
+        item
+    }
+
+    pub fn set_tree(&mut self, tree: WeaponTree) {
+        self.pending_tree = None;
+        let path = tree.upgrade_path(self.id);
+        if path.is_empty() {
+            return;
+        }
+        self.levels = path
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| WishlistLevel {
+                label: node.name.clone(),
+                materials: if idx == 0 {
+                    node.crafting.crafting_materials.clone()
+                } else {
+                    node.crafting.upgrade_materials.clone()
+                },
+            })
+            .collect();
+        self.target = self.levels.len() as i32 - 1;
+    }
+
+    pub fn from_charm(charm: &CharmInfo) -> Self {
+        let levels = charm
+            .ranks
+            .iter()
+            .map(|rank| WishlistLevel {
+                label: rank.name.clone(),
+                materials: rank.crafting.materials.clone(),
+            })
+            .collect();
+        Self::new(
+            SearchCategory::Charms,
+            charm.id,
+            charm.name.as_str(),
+            levels,
+        )
+    }
+
+    pub fn costs(&self) -> impl Iterator<Item = &CraftingCost> {
+        let count = (self.target + 1).max(0) as usize;
+        self.levels
+            .iter()
+            .take(count)
+            .flat_map(|level| level.materials.iter())
+    }
+}
+
+//
+// Wishlist
+//
+#[derive(Debug, Default)]
+pub struct Wishlist {
+    pub items: Vec<WishlistItem>,
+    pub owned: BTreeMap<i32, i32>, // item id to how many we already have
+    owned_path: Option<PathBuf>,   // where owned counts are saved, if anywhere
+}
+
+impl Wishlist {
+    // Owned counts are kept between runs; a missing file just means nothing is owned yet
+    pub fn load(path: &Path) -> (Self, Option<MHWQueryError>) {
+        let mut wishlist = Self {
+            owned_path: Some(path.to_owned()),
+            ..Default::default()
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return (wishlist, None),
+            Err(e) => {
+                let message = format!("Unable to read owned items: {}", e);
+                return (wishlist, Some(MHWQueryError::Internal(message)));
+            }
+        };
+        match serde_json::from_str(text.as_str()) {
+            Ok(owned) => {
+                wishlist.owned = owned;
+                (wishlist, None)
+            }
+            Err(e) => {
+                let path = path.display().to_string();
+                (wishlist, Some(MHWQueryError::from_decode(path, e)))
+            }
+        }
+    }
+
+    fn save_owned(&self) -> Result<(), MHWQueryError> {
+        let path = match &self.owned_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text = match serde_json::to_string(&self.owned) {
+            Ok(t) => t,
+            Err(e) => {
+                return Err(MHWQueryError::Internal(format!(
+                    "Unable to serialize owned items: {}",
+                    e
+                )))
+            }
+        };
+        match fs::write(path, text) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(MHWQueryError::Internal(format!(
+                "Unable to write owned items: {}",
+                e
+            ))),
+        }
+    }
+
+    // Adding something already on the list replaces it
+    pub fn add(&mut self, item: WishlistItem) {
+        self.items
+            .retain(|other| other.category != item.category || other.id != item.id);
+        self.items.push(item);
+    }
+
+    pub fn materials(&self) -> Vec<CraftingCost> {
+        merge_costs(self.items.iter().flat_map(WishlistItem::costs))
+    }
+
+    fn layout_items<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        let mut removed = None;
+        for (idx, item) in self.items.iter_mut().enumerate() {
+            if let Some(weapon_type) = item.pending_tree {
+                if let Some(nodes) = details.catalog.weapon_tree(weapon_type, event_queue) {
+                    item.set_tree(WeaponTree::new(nodes));
+                }
+            }
+            ui.with_id(idx as i32, || {
+                if ui.small_button(im_str!("x")) {
+                    removed = Some(idx);
+                }
+                ui.same_line(0.0);
+                if ui.small_button(&ImString::new(item.name.as_str())) {
+                    let request = EntryRequest::new(item.category, item.id);
+                    event_queue.push_back(MhwEvent::RequestEntry(request));
+                }
+
+                if item.levels.len() > 1 {
+                    let labels = item
+                        .levels
+                        .iter()
+                        .map(|level| ImString::new(level.label.as_str()))
+                        .collect::<Vec<_>>();
+                    let ref_labels = labels
+                        .iter()
+                        .map(std::convert::AsRef::as_ref)
+                        .collect::<Vec<_>>();
+                    ui.same_line(0.0);
+                    ui.with_item_width(-1.0, || {
+                        ui.combo(
+                            im_str!("##target_level"),
+                            &mut item.target,
+                            ref_labels.as_slice(),
+                            ref_labels.len() as i32,
+                        );
+                    });
+                }
+            });
+        }
+        if let Some(idx) = removed {
+            self.items.remove(idx);
+        }
+    }
+
+    fn layout_materials<'a>(&mut self, ui: &Ui<'a>, event_queue: &mut VecDeque<MhwEvent>) {
+        let materials = self.materials();
+        if materials.is_empty() {
+            ui.text("<none>");
+            return;
+        }
+
+        ui.columns(4, im_str!("wishlist_materials"), true);
+        for title in &["Item", "Needed", "Owned", "Missing"] {
+            ui.with_font(FONT_IDX_WINDOW_TITLE, || ui.text(title));
+            ui.next_column();
+        }
+        ui.separator();
+        let mut changed = false;
+        for cost in &materials {
+            ui.with_id(cost.item.id, || {
+                let label = ImString::new(cost.item.name.as_str());
+                if ui.selectable(&label, false, ImGuiSelectableFlags::empty(), (0.0, 0.0)) {
+                    let request = EntryRequest::new(SearchCategory::Items, cost.item.id);
+                    event_queue.push_back(MhwEvent::RequestEntry(request));
+                }
+                ui.next_column();
+                ui.text(cost.quantity.to_string());
+                ui.next_column();
+
+                let owned = self.owned.entry(cost.item.id).or_insert(0);
+                ui.with_item_width(-1.0, || {
+                    changed |= ui.input_int(im_str!("##owned"), owned).build();
+                });
+                *owned = (*owned).max(0);
+                ui.next_column();
+
+                let missing = (cost.quantity - *owned).max(0);
+                if missing > 0 {
+                    ui.text(missing.to_string());
+                } else {
+                    ui.text("-");
+                }
+                ui.next_column();
+            });
+        }
+        ui.columns(1, im_str!("wishlist_end"), false);
+
+        if changed {
+            if let Err(e) = self.save_owned() {
+                event_queue.push_back(MhwEvent::ShowError("Saving owned items failed", e));
+            }
+        }
+    }
+}
+
+impl MhwGui for Wishlist {
+    fn layout<'a>(
+        &mut self,
+        ui: &Ui<'a>,
+        details: &mut GuiDetails,
+        event_queue: &mut VecDeque<MhwEvent>,
+    ) {
+        if !details.draw_wishlist_window {
+            return;
+        }
+
+        let draw_cursor_pos = details.next_start_pos;
+        let mut opened = true;
+        let window = ui
+            .window(im_str!("Wishlist"))
+            .position(
+                (draw_cursor_pos.0 + 80.0, draw_cursor_pos.1 + 80.0),
+                ImGuiCond::FirstUseEver,
+            )
+            .size((520.0, 560.0), ImGuiCond::FirstUseEver)
+            .opened(&mut opened)
+            .flags(ImGuiWindowFlags::NoCollapse);
+
+        window.build(|| {
+            ui.with_font(FONT_IDX_NORMAL, || {
+                if self.items.is_empty() {
+                    ui.text_wrapped(im_str!(
+                        "Add armor, weapons and charms from their views to plan what to farm."
+                    ));
+                    return;
+                }
+
+                self.layout_items(ui, details, event_queue);
+                ui.separator();
+                self.layout_materials(ui, event_queue);
+            });
+        });
+        details.draw_wishlist_window = opened;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::data_source::fixture_source;
+    use super::super::query::QueryInfo;
+    use super::super::weapon_tree::load_weapon_tree;
+    use super::*;
+    use std::env;
+
+    fn fixture_weapons() -> Vec<WeaponInfo> {
+        QueryInfo::find_category(SearchCategory::Weapons)
+            .execute(&fixture_source())
+            .unwrap()
+    }
+
+    fn labels(item: &WishlistItem) -> Vec<&str> {
+        item.levels
+            .iter()
+            .map(|level| level.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn weapons_fill_in_their_path_once_the_tree_loads() {
+        let weapon = fixture_weapons().remove(1);
+        let mut item = WishlistItem::from_weapon(&weapon, None);
+        assert_eq!(item.pending_tree, Some(WeaponType::GreatSword));
+        assert_eq!(labels(&item), vec!["Buster Sword 2"]);
+
+        let nodes = load_weapon_tree(&fixture_source(), WeaponType::GreatSword).unwrap();
+        item.set_tree(WeaponTree::new(&nodes));
+        assert_eq!(item.pending_tree, None);
+        assert_eq!(labels(&item), vec!["Buster Sword 1", "Buster Sword 2"]);
+        assert_eq!(item.target, 1);
+
+        let loaded = WishlistItem::from_weapon(&weapon, Some(WeaponTree::new(&nodes)));
+        assert_eq!(loaded.pending_tree, None);
+        assert_eq!(labels(&loaded), labels(&item));
+    }
+
+    #[test]
+    fn owned_counts_are_saved_and_loaded() {
+        let path = env::temp_dir().join(format!("mhw_owned_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let (mut wishlist, error) = Wishlist::load(&path);
+        assert!(error.is_none());
+        assert!(wishlist.owned.is_empty());
+
+        wishlist.owned.insert(107, 4);
+        wishlist.owned.insert(101, 1);
+        wishlist.save_owned().unwrap();
+        let (reloaded, error) = Wishlist::load(&path);
+        assert!(error.is_none());
+        assert_eq!(reloaded.owned, wishlist.owned);
+
+        fs::write(&path, "not json").unwrap();
+        let (broken, error) = Wishlist::load(&path);
+        assert!(broken.owned.is_empty());
+        assert!(error.is_some());
+        fs::remove_file(&path).unwrap();
+    }
+}
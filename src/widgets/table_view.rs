@@ -1,18 +1,24 @@
-use crate::mhw::common::fonts::*;
+use crate::mhw::common::{fonts::*, MhwEvent};
+use crate::mhw::entry_display::EntryRequest;
 use imgui::*;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 
 pub trait TableDataModel {
     fn is_empty(&self) -> bool;
     fn col_count(&self) -> usize;
     fn row_count(&self) -> usize;
-    fn draw_cell(&self, ui: &Ui, col: usize, row: usize);
+    // Returns true if the cell was clicked
+    fn draw_cell(&self, ui: &Ui, col: usize, row: usize) -> bool;
+    // The entry a cell opens when clicked, if any
+    fn cell_link(&self, col: usize, row: usize) -> Option<EntryRequest>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimpleTableDataModel {
     col_count: usize,
     data: Vec<String>,
+    links: Vec<(usize, EntryRequest)>, // indices into data drawn as selectables
 }
 
 impl SimpleTableDataModel {
@@ -20,6 +26,7 @@ impl SimpleTableDataModel {
         SimpleTableDataModel {
             col_count,
             data: vec![],
+            links: vec![],
         }
     }
 
@@ -31,6 +38,12 @@ impl SimpleTableDataModel {
         self.data.push(cell);
     }
 
+    // Clicking the cell opens `link`
+    pub fn push_link(&mut self, cell: String, link: EntryRequest) {
+        self.links.push((self.data.len(), link));
+        self.data.push(cell);
+    }
+
     pub fn pop(&mut self) -> Option<String> {
         self.data.pop()
     }
@@ -60,22 +73,35 @@ impl TableDataModel for SimpleTableDataModel {
             return 0;
         }
 
-        let extra = if self.data.len() % self.col_count() > 0 {
-            1
-        } else {
-            0
-        };
-        self.data.len() / self.col_count as usize + extra
+        // a partly filled last row still counts
+        self.data.len().div_ceil(self.col_count)
     }
 
-    fn draw_cell(&self, ui: &Ui, col: usize, row: usize) {
+    fn draw_cell(&self, ui: &Ui, col: usize, row: usize) -> bool {
         if col >= self.col_count() {
-            return;
+            return false;
         }
-        if let Some(datum) = self.data.get(row * self.col_count + col) {
-            ui.text(datum.as_str());
+        let idx = row * self.col_count + col;
+        match self.data.get(idx) {
+            Some(datum) if self.cell_link(col, row).is_some() => {
+                let label = ImString::from(format!("{}##cell{}", datum, idx));
+                ui.selectable(&label, false, ImGuiSelectableFlags::empty(), (0.0, 0.0))
+            }
+            Some(datum) => {
+                ui.text(datum.as_str());
+                false
+            }
+            None => false,
         }
     }
+
+    fn cell_link(&self, col: usize, row: usize) -> Option<EntryRequest> {
+        let idx = row * self.col_count + col;
+        self.links
+            .iter()
+            .find(|(link_idx, _)| *link_idx == idx)
+            .map(|(_, link)| *link)
+    }
 }
 
 #[derive(Debug)]
@@ -87,7 +113,7 @@ pub struct CompoundTableDataModel<T: TableDataModel + Debug> {
 }
 
 impl<T: TableDataModel + Debug> CompoundTableDataModel<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         CompoundTableDataModel {
             max_cols: 0,
             total_rows: 0,
@@ -95,7 +121,7 @@ impl<T: TableDataModel + Debug> CompoundTableDataModel<T> {
         }
     }
 
-    fn push(&mut self, table: T) {
+    pub fn push(&mut self, table: T) {
         let col_count = table.col_count();
         if col_count > self.max_cols {
             self.max_cols = col_count
@@ -105,6 +131,12 @@ impl<T: TableDataModel + Debug> CompoundTableDataModel<T> {
     }
 }
 
+impl<T: TableDataModel + Debug> Default for CompoundTableDataModel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: TableDataModel + Debug> TableDataModel for CompoundTableDataModel<T> {
     fn is_empty(&self) -> bool {
         self.tables.is_empty()
@@ -118,26 +150,45 @@ impl<T: TableDataModel + Debug> TableDataModel for CompoundTableDataModel<T> {
         self.total_rows
     }
 
-    fn draw_cell(&self, ui: &Ui, col: usize, row: usize) {
+    fn draw_cell(&self, ui: &Ui, col: usize, row: usize) -> bool {
         if self.tables.is_empty() {
-            return;
+            return false;
         }
         if col >= self.col_count() {
-            return;
+            return false;
+        }
+
+        let mut actual_row = row;
+        for table in &self.tables {
+            if table.row_count() <= actual_row {
+                actual_row -= table.row_count();
+            } else {
+                return table.draw_cell(ui, col, actual_row);
+            }
         }
+        false
+    }
 
+    fn cell_link(&self, col: usize, row: usize) -> Option<EntryRequest> {
         let mut actual_row = row;
         for table in &self.tables {
-            if table.row_count() <= row {
+            if table.row_count() <= actual_row {
                 actual_row -= table.row_count();
             } else {
-                table.draw_cell(ui, col, actual_row);
+                return table.cell_link(col, actual_row);
             }
         }
+        None
     }
 }
 
-pub fn draw_table(ui: &Ui, title: &str, data_model: &TableDataModel) {
+// Clicking a linked cell opens its entry
+pub fn draw_table(
+    ui: &Ui,
+    title: &str,
+    data_model: &TableDataModel,
+    event_queue: &mut VecDeque<MhwEvent>,
+) {
     // TODO: use a clip rect to make sure titles don't get drawn outside when narrow
     // TODO: don't draw it when its width is negative...?
 
@@ -188,7 +239,11 @@ pub fn draw_table(ui: &Ui, title: &str, data_model: &TableDataModel) {
             ui.with_font(FONT_IDX_NORMAL, || {
                 for row in 0..row_count {
                     for col in 0..col_count {
-                        data_model.draw_cell(ui, col, row);
+                        if data_model.draw_cell(ui, col, row) {
+                            if let Some(link) = data_model.cell_link(col, row) {
+                                event_queue.push_back(MhwEvent::RequestEntry(link));
+                            }
+                        }
                         ui.next_column();
                     }
                 }
@@ -212,3 +267,50 @@ pub fn draw_table(ui: &Ui, title: &str, data_model: &TableDataModel) {
         .build();
     ui.set_cursor_screen_pos((new_cursor.0, new_cursor.1 + frame_padding.y));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mhw::search::SearchCategory;
+
+    fn table(rows: &[(&str, Option<i32>)]) -> SimpleTableDataModel {
+        let mut table = SimpleTableDataModel::new(2);
+        for (name, id) in rows {
+            match id {
+                Some(id) => {
+                    let link = EntryRequest::new(SearchCategory::Items, *id);
+                    table.push_link((*name).to_owned(), link);
+                }
+                None => table.push((*name).to_owned()),
+            }
+            table.push("x".to_owned());
+        }
+        table
+    }
+
+    #[test]
+    fn links_are_found_by_cell() {
+        let table = table(&[("Iron Ore", Some(107)), ("Zenny", None)]);
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.cell_link(0, 0).map(|link| link.id), Some(107));
+        assert!(table.cell_link(1, 0).is_none());
+        assert!(table.cell_link(0, 1).is_none());
+        assert!(table.cell_link(0, 2).is_none());
+    }
+
+    #[test]
+    fn compound_rows_map_into_each_table() {
+        let mut compound = CompoundTableDataModel::new();
+        compound.push(table(&[("Iron Ore", Some(107)), ("Zenny", None)]));
+        compound.push(table(&[("Leather", Some(105))]));
+        compound.push(table(&[("Bone", None), ("Rathalos Plate", Some(102))]));
+        assert_eq!(compound.row_count(), 5);
+        assert_eq!(compound.col_count(), 2);
+
+        let links = (0..compound.row_count())
+            .map(|row| compound.cell_link(0, row).map(|link| link.id))
+            .collect::<Vec<_>>();
+        assert_eq!(links, vec![Some(107), None, Some(105), None, Some(102)]);
+        assert!(compound.cell_link(0, 5).is_none());
+    }
+}